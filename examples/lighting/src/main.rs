@@ -122,6 +122,9 @@ pub async fn run() {
 
     // main loop
     let mut shadows_enabled = true;
+    let mut ambient_occlusion_enabled = false;
+    let mut temporal_anti_aliasing_enabled = false;
+    let mut temporal_anti_aliasing: Option<TemporalAntiAliasing> = None;
     let mut lighting_model = LightingModel::Blinn;
     let mut material_type = MaterialType::Forward;
 
@@ -177,6 +180,31 @@ pub async fn run() {
                         }
                     }
 
+                    if ui
+                        .checkbox(&mut ambient_occlusion_enabled, "Ambient occlusion")
+                        .clicked()
+                    {
+                        ambient.ambient_occlusion = if ambient_occlusion_enabled {
+                            Some(ScreenSpaceAmbientOcclusion::new(&context))
+                        } else {
+                            None
+                        };
+                    }
+
+                    if ui
+                        .checkbox(
+                            &mut temporal_anti_aliasing_enabled,
+                            "Temporal anti-aliasing",
+                        )
+                        .clicked()
+                    {
+                        temporal_anti_aliasing = if temporal_anti_aliasing_enabled {
+                            Some(TemporalAntiAliasing::new(&context))
+                        } else {
+                            None
+                        };
+                    }
+
                     ui.label("Lighting model");
                     ui.radio_value(&mut lighting_model, LightingModel::Phong, "Phong");
                     ui.radio_value(&mut lighting_model, LightingModel::Blinn, "Blinn");
@@ -254,6 +282,42 @@ pub async fn run() {
             spot0.generate_shadow_map(1024, &model);
         }
 
+        // With temporal anti-aliasing, the forward and deferred materials are rendered into textures with the size of the viewport
+        // using a jittered camera and the anti-aliased result is copied to the screen
+        let mut anti_aliasing = match material_type {
+            MaterialType::Forward | MaterialType::Deferred => temporal_anti_aliasing.as_mut(),
+            _ => None,
+        };
+        let mut scene_camera = camera.clone();
+        if let Some(ref mut anti_aliasing) = anti_aliasing {
+            scene_camera.set_viewport(Viewport::new_at_origo(viewport.width, viewport.height));
+            scene_camera.tone_mapping = ToneMapping::None;
+            scene_camera.target_color_space = ColorSpace::Compute;
+            anti_aliasing.jitter(&mut scene_camera);
+        }
+
+        if let Some(ref mut ambient_occlusion) = ambient.ambient_occlusion {
+            // Render the depth with the same camera into a texture with the size of the viewport
+            let mut depth_camera = scene_camera.clone();
+            depth_camera.set_viewport(Viewport::new_at_origo(viewport.width, viewport.height));
+            let mut depth_texture = context.transient_depth_texture_2d::<f32>(
+                viewport.width,
+                viewport.height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            );
+            depth_texture
+                .as_depth_target()
+                .clear(ClearState::default())
+                .render_with_material(
+                    &DepthMaterial::default(),
+                    &depth_camera,
+                    model.into_iter().chain(&plane),
+                    &[],
+                );
+            ambient_occlusion.update(&scene_camera, DepthTexture::Single(&depth_texture), None);
+        }
+
         let lights = [
             &ambient as &dyn Light,
             &spot0,
@@ -333,11 +397,23 @@ pub async fn run() {
                 });
             }
             MaterialType::Forward => {
-                screen.render(&camera, model.into_iter().chain(&plane), &lights);
+                render_scene(
+                    &context,
+                    &screen,
+                    &camera,
+                    &scene_camera,
+                    anti_aliasing,
+                    model.into_iter().chain(&plane),
+                    &lights,
+                );
             }
             MaterialType::Deferred => {
-                screen.render(
+                render_scene(
+                    &context,
+                    &screen,
                     &camera,
+                    &scene_camera,
+                    anti_aliasing,
                     deferred_model.into_iter().chain(&deferred_plane),
                     &lights,
                 );
@@ -348,3 +424,56 @@ pub async fn run() {
         FrameOutput::default()
     });
 }
+
+///
+/// Renders the objects to the screen or, if temporal anti-aliasing is enabled,
+/// into textures using the scene camera and then copies the anti-aliased result to the screen.
+///
+fn render_scene(
+    context: &Context,
+    screen: &RenderTarget,
+    camera: &Camera,
+    scene_camera: &Camera,
+    temporal_anti_aliasing: Option<&mut TemporalAntiAliasing>,
+    objects: impl IntoIterator<Item = impl Object>,
+    lights: &[&dyn Light],
+) {
+    if let Some(temporal_anti_aliasing) = temporal_anti_aliasing {
+        let viewport = scene_camera.viewport();
+        let mut color_texture = context.transient_texture_2d::<[f16; 4]>(
+            viewport.width,
+            viewport.height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth_texture = context.transient_depth_texture_2d::<f32>(
+            viewport.width,
+            viewport.height,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        RenderTarget::new(
+            color_texture.as_color_target(None),
+            depth_texture.as_depth_target(),
+        )
+        .clear(ClearState::default())
+        .render(scene_camera, objects, lights);
+        temporal_anti_aliasing.update(
+            scene_camera,
+            ColorTexture::Single(&color_texture),
+            DepthTexture::Single(&depth_texture),
+        );
+        screen.apply_screen_effect(
+            &CopyEffect {},
+            camera,
+            &[],
+            temporal_anti_aliasing.texture().map(ColorTexture::Single),
+            None,
+        );
+    } else {
+        screen.render(camera, objects, lights);
+    }
+}
@@ -52,6 +52,7 @@ pub async fn run() {
     let max = aabb.max() - vec3(size.x * 0.1, size.y * 0.3, size.z * 0.4);
     let light_box = AxisAlignedBoundingBox::new_with_positions(&[min, max]);
     let mut lights = Vec::new();
+    let mut clustered_lights = ClusteredLights::new(&context);
 
     // main loop
     let mut intensity = 1.0;
@@ -71,7 +72,7 @@ pub async fn run() {
                 use three_d::egui::*;
                 SidePanel::left("side_panel").show(gui_context, |ui| {
                     ui.heading("Debug Panel");
-                    ui.add(Slider::new::<usize>(&mut light_count, 0..=50).text("Light count"));
+                    ui.add(Slider::new::<usize>(&mut light_count, 0..=200).text("Light count"));
                     ui.add(Slider::new::<f32>(&mut intensity, 0.0..=10.0).text("Light intensity"));
                     ui.add(
                         Slider::new::<f32>(&mut constant, 0.0..=10.0).text("Attenuation constant"),
@@ -121,6 +122,7 @@ pub async fn run() {
         camera.set_viewport(viewport);
 
         control.handle_events(&mut camera, &mut frame_input.events);
+        clustered_lights.update(&camera, lights.iter().map(|l| &l.light), []);
 
         frame_input
             .screen()
@@ -128,7 +130,7 @@ pub async fn run() {
             .render(
                 &camera,
                 lights.iter().map(|l| l.object()).chain(&model),
-                &lights.iter().map(|l| l.light()).collect::<Vec<_>>(),
+                &[&clustered_lights],
             )
             .write(|| {
                 gui.render();
@@ -198,8 +200,4 @@ impl Glow {
     pub fn object(&self) -> &dyn Object {
         &self.sphere
     }
-
-    pub fn light(&self) -> &dyn Light {
-        &self.light
-    }
 }
@@ -33,13 +33,11 @@ pub async fn run() {
         .unwrap();
     let cpu_point_cloud: PointCloud = loaded.deserialize("hand.pcd").unwrap();
 
-    let mut point_mesh = CpuMesh::sphere(4);
-    point_mesh.transform(&Mat4::from_scale(0.001)).unwrap();
-
-    let mut point_cloud = Gm {
-        geometry: InstancedMesh::new(&context, &cpu_point_cloud.into(), &point_mesh),
-        material: ColorMaterial::default(),
-    };
+    let mut point_cloud = Gm::new(
+        Mesh::from_point_cloud(&context, &cpu_point_cloud),
+        ColorMaterial::default(),
+    );
+    point_cloud.set_point_size(3.0);
     let c = -point_cloud.aabb().center();
     point_cloud.set_transformation(Mat4::from_translation(c));
 
@@ -85,18 +85,9 @@ pub async fn run() {
             .render(
                 &camera,
                 axes.into_iter()
-                    .chain(&Gm {
-                        geometry: &billboards,
-                        material: &material,
-                    })
-                    .chain(&Gm {
-                        geometry: &sprites_up,
-                        material: &material,
-                    })
-                    .chain(&Gm {
-                        geometry: &sprites,
-                        material: &material,
-                    }),
+                    .chain(&Gm::new(&billboards, &material))
+                    .chain(&Gm::new(&sprites_up, &material))
+                    .chain(&Gm::new(&sprites, &material)),
                 &[&ambient],
             );
 
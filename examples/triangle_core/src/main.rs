@@ -1,5 +1,6 @@
 use three_d::core::{
-    degrees, radians, vec3, ClearState, Color, Context, Mat4, Program, RenderStates, VertexBuffer,
+    degrees, radians, vec3, ClearState, Color, Context, Mat4, PrimitiveType, Program, RenderStates,
+    VertexBuffer,
 };
 use three_d::window::{FrameOutput, Window, WindowSettings};
 use three_d_asset::Camera;
@@ -68,6 +69,7 @@ pub fn main() {
                 program.draw_arrays(
                     RenderStates::default(),
                     frame_input.viewport,
+                    PrimitiveType::Triangles,
                     positions.vertex_count(),
                 );
             });
@@ -28,6 +28,29 @@ mod program;
 #[doc(inline)]
 pub use program::*;
 
+mod program_cache;
+#[doc(inline)]
+pub use program_cache::*;
+
+mod program_binary;
+use program_binary::*;
+
+mod shader_chunks;
+#[doc(inline)]
+pub use shader_chunks::*;
+
+mod profiler;
+#[doc(inline)]
+pub use profiler::*;
+
+mod render_stats;
+#[doc(inline)]
+pub use render_stats::*;
+
+mod texture_pool;
+#[doc(inline)]
+pub use texture_pool::*;
+
 mod scissor_box;
 #[doc(inline)]
 pub use scissor_box::*;
@@ -59,6 +82,12 @@ pub enum CoreError {
     ShaderCompilation(String, String, String),
     #[error("failed to link shader program: {0}")]
     ShaderLink(String),
+    #[error("failed creating shader program: {0}")]
+    ProgramCreation(String),
+    #[error("the shader chunk {0} is included but has not been registered")]
+    MissingShaderChunk(String),
+    #[error("the shader chunk {0} is built-in and cannot be replaced or removed")]
+    ReservedShaderChunk(String),
 }
 
 ///
@@ -99,7 +128,7 @@ pub fn apply_effect(
             |program| {
                 use_uniforms(program);
                 program.use_vertex_attribute("position", &position_buffer);
-                program.draw_arrays(render_states, viewport, 3);
+                program.draw_arrays(render_states, viewport, PrimitiveType::Triangles, 3);
             },
         )
         .expect("Failed compiling shader");
@@ -148,7 +177,7 @@ pub fn apply_cube_effect(
                 program.use_uniform("direction", side.direction());
                 program.use_uniform("up", side.up());
                 program.use_vertex_attribute("position", &position_buffer);
-                program.draw_arrays(render_states, viewport, 3);
+                program.draw_arrays(render_states, viewport, PrimitiveType::Triangles, 3);
             },
         )
         .expect("Failed compiling shader");
@@ -173,7 +202,7 @@ pub(crate) fn full_screen_draw(
         ],
     );
     program.use_vertex_attribute("position", &position_buffer);
-    program.draw_arrays(render_states, viewport, 3);
+    program.draw_arrays(render_states, viewport, PrimitiveType::Triangles, 3);
 }
 
 pub(crate) fn full_screen_vertex_shader_source() -> &'static str {
@@ -16,8 +16,14 @@ pub struct Context {
     context: Arc<crate::context::Context>,
     pub(super) vao: crate::context::VertexArray,
     programs_old: Arc<RwLock<HashMap<(String, String), Program>>>,
-    /// A cache of programs to avoid recompiling a [Program] every frame.
-    pub programs: Arc<RwLock<HashMap<Vec<u8>, Program>>>,
+    pub(crate) programs: Arc<RwLock<ProgramCache>>,
+    pub(super) program_binaries: Arc<ProgramBinaryCache>,
+    /// The named shader chunks which can be included in the shader source of a [Program] (see [ShaderChunks]).
+    pub shader_chunks: Arc<RwLock<ShaderChunks>>,
+    profiler: Arc<RwLock<Profiler>>,
+    pub(super) render_stats: Arc<RenderStatsCounter>,
+    pub(super) texture_pool: Arc<RwLock<TexturePool>>,
+    render_states_cache: Arc<RenderStatesCache>,
 }
 
 impl Context {
@@ -32,6 +38,8 @@ impl Context {
             if !context.version().is_embedded {
                 // Enable seamless cube map textures - not available on OpenGL ES and WebGL
                 context.enable(crate::context::TEXTURE_CUBE_MAP_SEAMLESS);
+                // Use the point size written by the vertex shader - always enabled on OpenGL ES and WebGL
+                context.enable(crate::context::PROGRAM_POINT_SIZE);
             }
             context.pixel_store_i32(crate::context::UNPACK_ALIGNMENT, 1);
             context.pixel_store_i32(crate::context::PACK_ALIGNMENT, 1);
@@ -45,7 +53,13 @@ impl Context {
                 context,
                 vao,
                 programs_old: Arc::new(RwLock::new(HashMap::new())),
-                programs: Arc::new(RwLock::new(HashMap::new())),
+                programs: Arc::new(RwLock::new(ProgramCache::new())),
+                program_binaries: Arc::new(ProgramBinaryCache::default()),
+                shader_chunks: Arc::new(RwLock::new(ShaderChunks::new())),
+                profiler: Arc::new(RwLock::new(Profiler::default())),
+                render_stats: Arc::new(RenderStatsCounter::default()),
+                texture_pool: Arc::new(RwLock::new(TexturePool::default())),
+                render_states_cache: Arc::new(RenderStatesCache::default()),
             }
         };
         Ok(c)
@@ -55,7 +69,7 @@ impl Context {
     /// Compiles a [Program] with the given vertex and fragment shader source and stores it for later use.
     /// If it has already been created, then it is just returned.
     ///
-    #[deprecated = "Use Context::programs"]
+    #[deprecated = "Use Context::prewarm_program"]
     pub fn program(
         &self,
         vertex_shader_source: String,
@@ -74,6 +88,303 @@ impl Context {
         Ok(())
     }
 
+    ///
+    /// Returns the number of programs in the cache of programs used to avoid recompiling a [Program] every frame (see [ProgramCache]).
+    ///
+    pub fn program_cache_len(&self) -> usize {
+        self.programs.read().unwrap().len()
+    }
+
+    ///
+    /// Returns the number of times a requested program was found in the program cache since the context was created or the statistics was reset (see [ProgramCache::hits]).
+    ///
+    pub fn program_cache_hits(&self) -> u64 {
+        self.programs.read().unwrap().hits()
+    }
+
+    ///
+    /// Returns the number of times a requested program was not found in the program cache and therefore had to be compiled
+    /// since the context was created or the statistics was reset (see [ProgramCache::misses]).
+    ///
+    pub fn program_cache_misses(&self) -> u64 {
+        self.programs.read().unwrap().misses()
+    }
+
+    ///
+    /// Resets the hit and miss counts of the program cache.
+    ///
+    pub fn reset_program_cache_statistics(&self) {
+        self.programs.write().unwrap().reset_statistics();
+    }
+
+    ///
+    /// Removes all programs from the program cache, for example after a [ShaderChunks] has been changed.
+    /// The hit and miss counts are not reset, use [Context::reset_program_cache_statistics] for that.
+    ///
+    pub fn clear_program_cache(&self) {
+        self.programs.write().unwrap().clear();
+    }
+
+    ///
+    /// Returns the maximum number of programs in the program cache or `None` if the cache is unbounded, which is the default.
+    ///
+    pub fn program_cache_capacity(&self) -> Option<usize> {
+        self.programs.read().unwrap().capacity()
+    }
+
+    ///
+    /// Sets the maximum number of programs in the program cache, `None` means that the cache is unbounded (see [ProgramCache::set_capacity]).
+    ///
+    pub fn set_program_cache_capacity(&self, capacity: Option<usize>) {
+        self.programs.write().unwrap().set_capacity(capacity);
+    }
+
+    ///
+    /// Creates a program using the `create` callback and inserts it into the program cache with the given key, unless a program with that key is already in the cache.
+    /// Use this to compile programs before they are needed, so that they are not compiled when rendering for the first time.
+    /// This does not affect the hit and miss counts of the cache.
+    ///
+    pub fn prewarm_program<E>(
+        &self,
+        key: Vec<u8>,
+        create: impl FnOnce() -> Result<Program, E>,
+    ) -> Result<(), E> {
+        if !self.programs.read().unwrap().contains(&key) {
+            let program = create()?;
+            self.programs.write().unwrap().insert(key, program);
+        }
+        Ok(())
+    }
+
+    ///
+    /// Loads the OpenGL functions needed to store and load program binaries, using the same loader function as used to create the low-level graphics context.
+    /// This is done automatically when using the [window](crate::window) module.
+    /// If the functions could not be loaded or the driver does not support any program binary format, program binaries are silently not used.
+    /// See [Context::set_program_binary_directory] for more information.
+    ///
+    /// # Safety
+    /// The loader function must return valid function pointers for the current OpenGL context or null pointers.
+    ///
+    #[cfg(not(target_arch = "wasm32"))]
+    pub unsafe fn load_program_binary_functions(
+        &self,
+        loader: impl FnMut(&str) -> *const std::ffi::c_void,
+    ) {
+        self.program_binaries.load_functions(&self.context, loader);
+    }
+
+    ///
+    /// Returns whether or not program binaries are supported by the driver and the functions needed are loaded (see [Context::load_program_binary_functions]).
+    /// Program binaries are never supported on web.
+    ///
+    pub fn supports_program_binaries(&self) -> bool {
+        self.program_binaries.is_supported()
+    }
+
+    ///
+    /// Sets the directory where the binaries of compiled shader programs are stored.
+    /// When a directory is set and [program binaries are supported](Context::supports_program_binaries), [Program::from_source] stores the binary of each compiled program in the directory
+    /// and next time a program with the exact same source is created on the same driver, the binary is loaded instead of compiling the program.
+    /// If a binary cannot be loaded, for example because the driver has been updated, the program is compiled from source as usual.
+    /// Use `None` to disable storing and loading program binaries, which is the default.
+    ///
+    pub fn set_program_binary_directory(&self, directory: Option<std::path::PathBuf>) {
+        self.program_binaries.set_directory(directory);
+    }
+
+    ///
+    /// Returns the directory where the binaries of compiled shader programs are stored, if any (see [Context::set_program_binary_directory]).
+    ///
+    pub fn program_binary_directory(&self) -> Option<std::path::PathBuf> {
+        self.program_binaries.directory()
+    }
+
+    ///
+    /// Returns whether or not timer queries, which are needed for profiling, are supported (see [Context::set_profiling_enabled]).
+    /// Timer queries are always supported on desktop, but requires the `EXT_disjoint_timer_query` extension on OpenGL ES and the `EXT_disjoint_timer_query_webgl2` extension on web.
+    ///
+    pub fn supports_profiling(&self) -> bool {
+        !self.version().is_embedded || {
+            let extensions = self.supported_extensions();
+            extensions.contains("EXT_disjoint_timer_query_webgl2")
+                || extensions.contains("GL_EXT_disjoint_timer_query")
+        }
+    }
+
+    ///
+    /// Enables or disables GPU profiling. Profiling is disabled by default and cannot be enabled if it is not [supported](Context::supports_profiling).
+    /// When enabled, the GPU time spent inside each [profiling scope](Context::profile_scope) is measured.
+    /// Disabling profiling discards all results that are not yet returned by [Context::profiling_results].
+    ///
+    pub fn set_profiling_enabled(&self, enabled: bool) {
+        let enabled = enabled && self.supports_profiling();
+        self.profiler
+            .write()
+            .unwrap()
+            .set_enabled(&self.context, enabled);
+    }
+
+    ///
+    /// Returns whether or not GPU profiling is enabled (see [Context::set_profiling_enabled]).
+    ///
+    pub fn is_profiling_enabled(&self) -> bool {
+        self.profiler.read().unwrap().is_enabled()
+    }
+
+    ///
+    /// Measures the GPU time spent on the rendering done in the given callback, for example a call to [RenderTarget::write], if profiling is enabled (see [Context::set_profiling_enabled]).
+    /// Scopes can be nested, in which case the time of a scope includes the time of the nested scopes.
+    /// The results are available a few frames later, since the GPU runs behind the CPU, and are returned by [Context::profiling_results].
+    ///
+    pub fn profile_scope<T>(&self, name: &str, callback: impl FnOnce() -> T) -> T {
+        self.begin_profile_scope(name);
+        let result = callback();
+        self.end_profile_scope();
+        result
+    }
+
+    ///
+    /// Begins a profiling scope with the given name, which ends when [Context::end_profile_scope] is called.
+    /// Prefer [Context::profile_scope] when possible, since it is not possible to forget to end the scope.
+    ///
+    pub fn begin_profile_scope(&self, name: &str) {
+        self.profiler.write().unwrap().begin(&self.context, name);
+    }
+
+    ///
+    /// Ends the profiling scope which was last begun with [Context::begin_profile_scope].
+    ///
+    pub fn end_profile_scope(&self) {
+        self.profiler.write().unwrap().end(&self.context);
+    }
+
+    ///
+    /// Returns the results of all top level profiling scopes which have finished on the GPU since the last call to this method, in the order they were started.
+    /// The nested scopes are returned as children of the top level scopes.
+    /// Should be called every frame while profiling is enabled, since the results of only a limited number of scopes are kept.
+    ///
+    pub fn profiling_results(&self) -> Vec<ProfileScope> {
+        self.profiler.write().unwrap().results(&self.context)
+    }
+
+    ///
+    /// Returns the [RenderStats] counted since the last call to [Context::reset_render_stats].
+    ///
+    pub fn render_stats(&self) -> RenderStats {
+        self.render_stats.current()
+    }
+
+    ///
+    /// Returns the [RenderStats] returned by the last call to [Context::reset_render_stats], ie. typically the statistics for the previous frame.
+    ///
+    pub fn previous_render_stats(&self) -> RenderStats {
+        self.render_stats.previous()
+    }
+
+    ///
+    /// Resets the [RenderStats] and returns the statistics counted since the last reset.
+    /// This is done at the beginning of each frame when using the [window](crate::window) module,
+    /// so the statistics for the previous frame is available using [Context::previous_render_stats].
+    ///
+    pub fn reset_render_stats(&self) -> RenderStats {
+        self.render_stats.reset()
+    }
+
+    ///
+    /// Returns a [Texture2D] with the given parameters from the texture pool of this context, or creates a new texture if no such texture is available in the pool.
+    /// The texture is returned to the pool when the returned [TransientTexture] is dropped.
+    /// Use this for textures which are only needed for a short while, for example the intermediate textures used when applying an effect like [FxaaEffect](crate::renderer::FxaaEffect).
+    /// See [Texture2D::new_empty] for a description of the parameters.
+    ///
+    pub fn transient_texture_2d<T: TextureDataType>(
+        &self,
+        width: u32,
+        height: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> TransientTexture<Texture2D> {
+        TransientTexture::new_texture_2d::<T>(
+            self,
+            width,
+            height,
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+        )
+    }
+
+    ///
+    /// Returns a [Texture2DArray] with the given parameters from the texture pool of this context, or creates a new texture if no such texture is available in the pool.
+    /// The texture is returned to the pool when the returned [TransientTexture] is dropped.
+    /// See [Texture2DArray::new_empty] for a description of the parameters.
+    ///
+    pub fn transient_texture_2d_array<T: TextureDataType>(
+        &self,
+        width: u32,
+        height: u32,
+        depth: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> TransientTexture<Texture2DArray> {
+        TransientTexture::new_texture_2d_array::<T>(
+            self,
+            width,
+            height,
+            depth,
+            min_filter,
+            mag_filter,
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+        )
+    }
+
+    ///
+    /// Returns a [DepthTexture2D] with the given parameters from the texture pool of this context, or creates a new texture if no such texture is available in the pool.
+    /// The texture is returned to the pool when the returned [TransientTexture] is dropped.
+    /// See [DepthTexture2D::new] for a description of the parameters.
+    ///
+    pub fn transient_depth_texture_2d<T: DepthTextureDataType>(
+        &self,
+        width: u32,
+        height: u32,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> TransientTexture<DepthTexture2D> {
+        TransientTexture::new_depth_texture_2d::<T>(self, width, height, wrap_s, wrap_t)
+    }
+
+    ///
+    /// Releases the textures in the texture pool which have not been returned to the pool since the last call to this method.
+    /// This is done at the beginning of each frame when using the [window](crate::window) module,
+    /// which means that the textures used for the previous viewport size are released shortly after the viewport size changes.
+    ///
+    pub fn trim_texture_pool(&self) {
+        self.texture_pool.write().unwrap().trim();
+    }
+
+    ///
+    /// Releases all textures in the texture pool. Textures which are currently borrowed are returned to the pool as usual when dropped.
+    ///
+    pub fn clear_texture_pool(&self) {
+        self.texture_pool.write().unwrap().clear();
+    }
+
+    ///
+    /// Returns the number of textures available in the texture pool, ie. not including the textures which are currently borrowed.
+    ///
+    pub fn texture_pool_size(&self) -> usize {
+        self.texture_pool.read().unwrap().len()
+    }
+
     ///
     /// Set the scissor test for this context (see [ScissorBox]).
     ///
@@ -109,8 +420,15 @@ impl Context {
 
     ///
     /// Set the face culling for this context (see [Cull]).
+    /// Does nothing if the face culling is already set to the given value and the render states cache is enabled, see [Context::set_render_states_cache].
     ///
     pub fn set_cull(&self, cull: Cull) {
+        if !self
+            .render_states_cache
+            .update(&self.render_states_cache.cull, cull as u32 + 1)
+        {
+            return;
+        }
         unsafe {
             match cull {
                 Cull::None => {
@@ -149,8 +467,15 @@ impl Context {
 
     ///
     /// Set the depth test for this context (see [DepthTest]).
+    /// Does nothing if the depth test is already set to the given value and the render states cache is enabled, see [Context::set_render_states_cache].
     ///
     pub fn set_depth_test(&self, depth_test: DepthTest) {
+        if !self
+            .render_states_cache
+            .update(&self.render_states_cache.depth_test, depth_test as u32 + 2)
+        {
+            return;
+        }
         unsafe {
             self.enable(crate::context::DEPTH_TEST);
             match depth_test {
@@ -184,8 +509,33 @@ impl Context {
 
     ///
     /// Set the blend state for this context (see [Blend]).
+    /// Does nothing if the blend state is already set to the given value and the render states cache is enabled, see [Context::set_render_states_cache].
     ///
     pub fn set_blend(&self, blend: Blend) {
+        let key = if let Blend::Enabled {
+            source_rgb_multiplier,
+            source_alpha_multiplier,
+            destination_rgb_multiplier,
+            destination_alpha_multiplier,
+            rgb_equation,
+            alpha_equation,
+        } = blend
+        {
+            2 | (source_rgb_multiplier as u32) << 4
+                | (source_alpha_multiplier as u32) << 8
+                | (destination_rgb_multiplier as u32) << 12
+                | (destination_alpha_multiplier as u32) << 16
+                | (rgb_equation as u32) << 20
+                | (alpha_equation as u32) << 24
+        } else {
+            1
+        };
+        if !self
+            .render_states_cache
+            .update(&self.render_states_cache.blend, key)
+        {
+            return;
+        }
         unsafe {
             if let Blend::Enabled {
                 source_rgb_multiplier,
@@ -213,6 +563,75 @@ impl Context {
         }
     }
 
+    ///
+    /// Set the stencil test and stencil operations for this context (see [Stencil]).
+    ///
+    pub fn set_stencil(&self, stencil: Stencil) {
+        unsafe {
+            if let Stencil::Enabled { front, back } = stencil {
+                self.enable(crate::context::STENCIL_TEST);
+                for (face, state) in [(crate::context::FRONT, front), (crate::context::BACK, back)]
+                {
+                    self.stencil_func_separate(
+                        face,
+                        Self::stencil_const_from_function(state.function),
+                        state.reference as i32,
+                        state.read_mask,
+                    );
+                    self.stencil_op_separate(
+                        face,
+                        Self::stencil_const_from_operation(state.fail),
+                        Self::stencil_const_from_operation(state.depth_fail),
+                        Self::stencil_const_from_operation(state.pass),
+                    );
+                    self.stencil_mask_separate(face, state.write_mask);
+                }
+            } else {
+                self.disable(crate::context::STENCIL_TEST);
+            }
+        }
+    }
+
+    ///
+    /// Set the depth bias (polygon offset) for this context (see [DepthBias]).
+    ///
+    pub fn set_depth_bias(&self, depth_bias: DepthBias) {
+        unsafe {
+            if depth_bias == DepthBias::NONE {
+                self.disable(crate::context::POLYGON_OFFSET_FILL);
+            } else {
+                self.enable(crate::context::POLYGON_OFFSET_FILL);
+                self.polygon_offset(depth_bias.factor, depth_bias.units);
+            }
+        }
+    }
+
+    fn stencil_const_from_function(function: StencilFunction) -> u32 {
+        match function {
+            StencilFunction::Never => crate::context::NEVER,
+            StencilFunction::Less => crate::context::LESS,
+            StencilFunction::Equal => crate::context::EQUAL,
+            StencilFunction::LessOrEqual => crate::context::LEQUAL,
+            StencilFunction::Greater => crate::context::GREATER,
+            StencilFunction::NotEqual => crate::context::NOTEQUAL,
+            StencilFunction::GreaterOrEqual => crate::context::GEQUAL,
+            StencilFunction::Always => crate::context::ALWAYS,
+        }
+    }
+
+    fn stencil_const_from_operation(operation: StencilOperation) -> u32 {
+        match operation {
+            StencilOperation::Keep => crate::context::KEEP,
+            StencilOperation::Zero => crate::context::ZERO,
+            StencilOperation::Replace => crate::context::REPLACE,
+            StencilOperation::Increment => crate::context::INCR,
+            StencilOperation::IncrementWrap => crate::context::INCR_WRAP,
+            StencilOperation::Decrement => crate::context::DECR,
+            StencilOperation::DecrementWrap => crate::context::DECR_WRAP,
+            StencilOperation::Invert => crate::context::INVERT,
+        }
+    }
+
     fn blend_const_from_multiplier(multiplier: BlendMultiplierType) -> u32 {
         match multiplier {
             BlendMultiplierType::Zero => crate::context::ZERO,
@@ -238,6 +657,44 @@ impl Context {
         }
     }
 
+    ///
+    /// Enables or disables the render states cache, which is disabled by default.
+    /// When enabled, the face culling, depth test and blend state last set using [Context::set_cull], [Context::set_depth_test] and [Context::set_blend] are remembered,
+    /// so that setting a state to the value it already has does nothing. This avoids redundant state changes when rendering many objects with the same render states.
+    /// The cache is invalidated at the start of each write to a render target (see [RenderTarget::write]),
+    /// but if any of these states are changed directly on the low-level graphics context while writing, call [Context::invalidate_render_states] afterwards.
+    ///
+    pub fn set_render_states_cache(&self, enabled: bool) {
+        self.invalidate_render_states();
+        self.render_states_cache
+            .enabled
+            .store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    ///
+    /// Returns whether the render states cache is enabled, see [Context::set_render_states_cache].
+    ///
+    pub fn render_states_cache(&self) -> bool {
+        self.render_states_cache
+            .enabled
+            .load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    ///
+    /// Forgets the face culling, depth test and blend state which was last set using [Context::set_cull], [Context::set_depth_test] and [Context::set_blend],
+    /// so that the next call to those methods changes the state even though it is set to the same value (see [Context::set_render_states_cache]).
+    /// Call this after changing any of these states directly on the low-level graphics context, for example when using another library for rendering.
+    ///
+    pub fn invalidate_render_states(&self) {
+        for state in [
+            &self.render_states_cache.cull,
+            &self.render_states_cache.depth_test,
+            &self.render_states_cache.blend,
+        ] {
+            state.store(0, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
     ///
     /// Set the render states for this context (see [RenderStates]).
     ///
@@ -245,11 +702,18 @@ impl Context {
         self.set_cull(render_states.cull);
         self.set_write_mask(render_states.write_mask);
         if !render_states.write_mask.depth && render_states.depth_test == DepthTest::Always {
-            unsafe { self.disable(crate::context::DEPTH_TEST) }
+            if self
+                .render_states_cache
+                .update(&self.render_states_cache.depth_test, 1)
+            {
+                unsafe { self.disable(crate::context::DEPTH_TEST) }
+            }
         } else {
             self.set_depth_test(render_states.depth_test);
         }
         self.set_blend(render_states.blend);
+        self.set_stencil(render_states.stencil);
+        self.set_depth_bias(render_states.depth_bias);
     }
 
     ///
@@ -320,6 +784,28 @@ impl Context {
     }
 }
 
+///
+/// The face culling, depth test and blend state last set on the [Context], see [Context::set_render_states_cache].
+/// Each state is encoded as a non-zero number, where zero means that the state is unknown and a depth test of one means that depth testing is disabled.
+///
+#[derive(Default)]
+struct RenderStatesCache {
+    enabled: std::sync::atomic::AtomicBool,
+    cull: std::sync::atomic::AtomicU32,
+    depth_test: std::sync::atomic::AtomicU32,
+    blend: std::sync::atomic::AtomicU32,
+}
+
+impl RenderStatesCache {
+    ///
+    /// Stores the encoded state if the cache is enabled and returns whether the state needs to be set, ie. if the cache is disabled or the state is changed.
+    ///
+    fn update(&self, state: &std::sync::atomic::AtomicU32, value: u32) -> bool {
+        !self.enabled.load(std::sync::atomic::Ordering::Relaxed)
+            || state.swap(value, std::sync::atomic::Ordering::Relaxed) != value
+    }
+}
+
 impl std::fmt::Debug for Context {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut d = f.debug_struct("Context");
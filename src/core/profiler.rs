@@ -0,0 +1,172 @@
+use crate::core::*;
+use std::collections::VecDeque;
+
+// Not defined in the context module since it is only part of the EXT_disjoint_timer_query extensions
+const GPU_DISJOINT: u32 = 0x8FBB;
+
+// The maximum number of top level scopes waiting for the results to be available, after which the oldest are discarded
+const MAX_PENDING: usize = 256;
+
+///
+/// The GPU time spent inside a named profiling scope, including the time spent in the nested scopes (see [Context::profile_scope]).
+///
+#[derive(Clone, Debug)]
+pub struct ProfileScope {
+    /// The name of the scope.
+    pub name: String,
+    /// The GPU time spent inside the scope in milliseconds.
+    pub time: f64,
+    /// The scopes nested directly inside this scope in the order they were started.
+    pub children: Vec<ProfileScope>,
+}
+
+struct Node {
+    name: String,
+    parent: Option<usize>,
+}
+
+struct Segment {
+    query: crate::context::Query,
+    // The nodes which were open while this segment was measured
+    nodes: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Tree {
+    nodes: Vec<Node>,
+    segments: Vec<Segment>,
+}
+
+///
+/// Measures the GPU time of nested scopes using timer queries.
+/// Since timer queries cannot be nested, a new query is started every time a scope begins or ends
+/// and the time measured by each query is added to all the scopes that were open at that time.
+///
+#[derive(Default)]
+pub(super) struct Profiler {
+    enabled: bool,
+    current: Tree,
+    open: Vec<usize>,
+    active_query: Option<crate::context::Query>,
+    pending: VecDeque<Tree>,
+    unused_queries: Vec<crate::context::Query>,
+}
+
+impl Profiler {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, context: &crate::context::Context, enabled: bool) {
+        if !enabled {
+            while !self.open.is_empty() {
+                self.end(context);
+            }
+            for tree in self.pending.drain(..) {
+                self.unused_queries
+                    .extend(tree.segments.into_iter().map(|s| s.query));
+            }
+        }
+        self.enabled = enabled;
+    }
+
+    pub fn begin(&mut self, context: &crate::context::Context, name: &str) {
+        if !self.enabled {
+            return;
+        }
+        self.end_segment(context);
+        self.current.nodes.push(Node {
+            name: name.to_string(),
+            parent: self.open.last().cloned(),
+        });
+        self.open.push(self.current.nodes.len() - 1);
+        self.begin_segment(context);
+    }
+
+    pub fn end(&mut self, context: &crate::context::Context) {
+        if !self.enabled || self.open.is_empty() {
+            return;
+        }
+        self.end_segment(context);
+        self.open.pop();
+        if self.open.is_empty() {
+            self.pending.push_back(std::mem::take(&mut self.current));
+            if self.pending.len() > MAX_PENDING {
+                let tree = self.pending.pop_front().unwrap();
+                self.unused_queries
+                    .extend(tree.segments.into_iter().map(|s| s.query));
+            }
+        } else {
+            self.begin_segment(context);
+        }
+    }
+
+    pub fn results(&mut self, context: &crate::context::Context) -> Vec<ProfileScope> {
+        let mut results = Vec::new();
+        while let Some(tree) = self.pending.front() {
+            let available = tree.segments.iter().all(|s| unsafe {
+                context.get_query_parameter_u32(s.query, crate::context::QUERY_RESULT_AVAILABLE)
+                    != 0
+            });
+            if !available {
+                break;
+            }
+            let tree = self.pending.pop_front().unwrap();
+            let disjoint = context.version().is_embedded
+                && unsafe { context.get_parameter_i32(GPU_DISJOINT) } != 0;
+            let mut times = vec![0u64; tree.nodes.len()];
+            for segment in tree.segments {
+                let time = unsafe {
+                    context.get_query_parameter_u32(segment.query, crate::context::QUERY_RESULT)
+                } as u64;
+                for node in segment.nodes {
+                    times[node] += time;
+                }
+                self.unused_queries.push(segment.query);
+            }
+            if !disjoint {
+                results.extend(Self::build(&tree.nodes, &times, None));
+            }
+        }
+        results
+    }
+
+    fn build(nodes: &[Node], times: &[u64], parent: Option<usize>) -> Vec<ProfileScope> {
+        nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent == parent)
+            .map(|(i, node)| ProfileScope {
+                name: node.name.clone(),
+                time: times[i] as f64 * 1e-6,
+                children: Self::build(nodes, times, Some(i)),
+            })
+            .collect()
+    }
+
+    fn begin_segment(&mut self, context: &crate::context::Context) {
+        let query = match self.unused_queries.pop() {
+            Some(query) => query,
+            None => match unsafe { context.create_query() } {
+                Ok(query) => query,
+                Err(_) => return,
+            },
+        };
+        unsafe {
+            context.begin_query(crate::context::TIME_ELAPSED, query);
+        }
+        self.active_query = Some(query);
+    }
+
+    fn end_segment(&mut self, context: &crate::context::Context) {
+        if let Some(query) = self.active_query.take() {
+            unsafe {
+                context.end_query(crate::context::TIME_ELAPSED);
+            }
+            self.current.segments.push(Segment {
+                query,
+                nodes: self.open.clone(),
+            });
+        }
+    }
+}
@@ -11,6 +11,7 @@ use std::sync::RwLock;
 pub struct Program {
     context: Context,
     id: crate::context::Program,
+    key: u32,
     attributes: HashMap<String, u32>,
     textures: RwLock<HashMap<String, u32>>,
     uniforms: HashMap<String, crate::context::UniformLocation>,
@@ -20,21 +21,29 @@ pub struct Program {
 impl Program {
     ///
     /// Creates a new shader program from the given vertex and fragment glsl shader source.
+    /// The shader source can include the chunks in [Context::shader_chunks] using an `#include "name"` directive on a separate line.
     ///
     pub fn from_source(
         context: &Context,
         vertex_shader_source: &str,
         fragment_shader_source: &str,
+    ) -> Result<Self, CoreError> {
+        Self::from_source_with_defines(context, vertex_shader_source, fragment_shader_source, &[])
+    }
+
+    ///
+    /// Creates a new shader program from the given vertex and fragment glsl shader source,
+    /// where each of the given defines, specified as a name and a value, is defined in both shaders using `#define name value`.
+    /// The shader source can include the chunks in [Context::shader_chunks] using an `#include "name"` directive on a separate line.
+    ///
+    pub fn from_source_with_defines(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        defines: &[(&str, &str)],
     ) -> Result<Self, CoreError> {
         unsafe {
-            let vert_shader = context
-                .create_shader(crate::context::VERTEX_SHADER)
-                .expect("Failed creating vertex shader");
-            let frag_shader = context
-                .create_shader(crate::context::FRAGMENT_SHADER)
-                .expect("Failed creating fragment shader");
-
-            let header: &str = if context.version().is_embedded {
+            let mut header = if context.version().is_embedded {
                 "#version 300 es
                     #ifdef GL_FRAGMENT_PRECISION_HIGH
                         precision highp float;
@@ -49,48 +58,42 @@ impl Program {
                     #endif\n"
             } else {
                 "#version 330 core\n"
-            };
-            let vertex_shader_source = format!("{}{}", header, vertex_shader_source);
-            let fragment_shader_source = format!("{}{}", header, fragment_shader_source);
-
-            context.shader_source(vert_shader, &vertex_shader_source);
-            context.shader_source(frag_shader, &fragment_shader_source);
-            context.compile_shader(vert_shader);
-            context.compile_shader(frag_shader);
-
-            let id = context.create_program().expect("Failed creating program");
-            context.attach_shader(id, vert_shader);
-            context.attach_shader(id, frag_shader);
-            context.link_program(id);
-
-            if !context.get_program_link_status(id) {
-                let log = context.get_shader_info_log(vert_shader);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderCompilation(
-                        "vertex".to_string(),
-                        log,
-                        vertex_shader_source,
-                    ))?;
-                }
-                let log = context.get_shader_info_log(frag_shader);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderCompilation(
-                        "fragment".to_string(),
-                        log,
-                        fragment_shader_source,
-                    ))?;
-                }
-                let log = context.get_program_info_log(id);
-                if !log.is_empty() {
-                    Err(CoreError::ShaderLink(log))?;
-                }
-                unreachable!();
             }
-
-            context.detach_shader(id, vert_shader);
-            context.detach_shader(id, frag_shader);
-            context.delete_shader(vert_shader);
-            context.delete_shader(frag_shader);
+            .to_string();
+            for (name, value) in defines {
+                header.push_str(&format!("#define {} {}\n", name, value));
+            }
+            let chunks = context.shader_chunks.read().unwrap();
+            let (vertex_shader_source, vertex_source_map) =
+                chunks.preprocess(&header, "vertex shader", vertex_shader_source)?;
+            let (fragment_shader_source, fragment_source_map) =
+                chunks.preprocess(&header, "fragment shader", fragment_shader_source)?;
+            drop(chunks);
+
+            let id = match context.program_binaries.load(
+                context,
+                &vertex_shader_source,
+                &fragment_shader_source,
+            ) {
+                Some(id) => id,
+                None => {
+                    let id = Self::compile(
+                        context,
+                        &vertex_shader_source,
+                        &fragment_shader_source,
+                        &vertex_source_map,
+                        &fragment_source_map,
+                    )?;
+                    context.render_stats.count_program_compiled();
+                    context.program_binaries.store(
+                        context,
+                        id,
+                        &vertex_shader_source,
+                        &fragment_shader_source,
+                    );
+                    id
+                }
+            };
 
             // Init vertex attributes
             let num_attribs = context.get_active_attributes(id);
@@ -131,6 +134,7 @@ impl Program {
             Ok(Program {
                 context: context.clone(),
                 id,
+                key: next_program_key(),
                 attributes,
                 uniforms,
                 uniform_blocks: RwLock::new(HashMap::new()),
@@ -139,6 +143,76 @@ impl Program {
         }
     }
 
+    unsafe fn compile(
+        context: &Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+        vertex_source_map: &SourceMap,
+        fragment_source_map: &SourceMap,
+    ) -> Result<crate::context::Program, CoreError> {
+        let vert_shader = context
+            .create_shader(crate::context::VERTEX_SHADER)
+            .map_err(CoreError::ProgramCreation)?;
+        let frag_shader = match context.create_shader(crate::context::FRAGMENT_SHADER) {
+            Ok(frag_shader) => frag_shader,
+            Err(e) => {
+                context.delete_shader(vert_shader);
+                Err(CoreError::ProgramCreation(e))?
+            }
+        };
+
+        context.shader_source(vert_shader, vertex_shader_source);
+        context.shader_source(frag_shader, fragment_shader_source);
+        context.compile_shader(vert_shader);
+        context.compile_shader(frag_shader);
+
+        let id = match context.create_program() {
+            Ok(id) => id,
+            Err(e) => {
+                context.delete_shader(vert_shader);
+                context.delete_shader(frag_shader);
+                Err(CoreError::ProgramCreation(e))?
+            }
+        };
+        context.attach_shader(id, vert_shader);
+        context.attach_shader(id, frag_shader);
+        context.program_binaries.set_retrievable_hint(id);
+        context.link_program(id);
+
+        if !context.get_program_link_status(id) {
+            let vertex_log = context.get_shader_info_log(vert_shader);
+            let fragment_log = context.get_shader_info_log(frag_shader);
+            let program_log = context.get_program_info_log(id);
+            context.detach_shader(id, vert_shader);
+            context.detach_shader(id, frag_shader);
+            context.delete_shader(vert_shader);
+            context.delete_shader(frag_shader);
+            context.delete_program(id);
+            if !vertex_log.is_empty() {
+                Err(CoreError::ShaderCompilation(
+                    "vertex".to_string(),
+                    vertex_source_map.map_log(&vertex_log),
+                    vertex_shader_source.to_string(),
+                ))?;
+            }
+            if !fragment_log.is_empty() {
+                Err(CoreError::ShaderCompilation(
+                    "fragment".to_string(),
+                    fragment_source_map.map_log(&fragment_log),
+                    fragment_shader_source.to_string(),
+                ))?;
+            }
+            Err(CoreError::ShaderLink(program_log))?;
+        }
+
+        context.detach_shader(id, vert_shader);
+        context.detach_shader(id, frag_shader);
+        context.delete_shader(vert_shader);
+        context.delete_shader(frag_shader);
+
+        Ok(id)
+    }
+
     ///
     /// Send the given uniform data to this shader program and associate it with the given named variable.
     /// The glsl shader variable must be of type `uniform int` if the data is an integer, `uniform vec2` if it is of type [Vec2] etc.
@@ -287,6 +361,7 @@ impl Program {
         };
         let index = *self.textures.read().unwrap().get(name).unwrap();
         self.use_uniform(name, index as i32);
+        self.context.render_stats.count_texture_bind();
         unsafe {
             self.context
                 .active_texture(crate::context::TEXTURE0 + index);
@@ -415,18 +490,27 @@ impl Program {
     }
 
     ///
-    /// Draws `count` number of triangles with the given render states and viewport using this shader program.
+    /// Draws `count` number of vertices as primitives of the given [PrimitiveType] (for example triangles) with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
-    /// Assumes that the data for the three vertices in a triangle is defined contiguous in each vertex buffer.
+    /// Assumes that the data for the vertices in a primitive is defined contiguous in each vertex buffer, for example the three vertices in a triangle.
     /// If you want to use an [ElementBuffer], see [Program::draw_elements].
     ///
-    pub fn draw_arrays(&self, render_states: RenderStates, viewport: Viewport, count: u32) {
+    pub fn draw_arrays(
+        &self,
+        render_states: RenderStates,
+        viewport: Viewport,
+        primitive_type: PrimitiveType,
+        count: u32,
+    ) {
         self.context.set_viewport(viewport);
         self.context.set_render_states(render_states);
         self.use_program();
         unsafe {
             self.context
-                .draw_arrays(crate::context::TRIANGLES, 0, count as i32);
+                .draw_arrays(primitive_type.to_const(), 0, count as i32);
+            self.context
+                .render_stats
+                .count_draw(primitive_type, count, 1);
             for location in self.attributes.values() {
                 self.context.disable_vertex_attrib_array(*location);
             }
@@ -441,13 +525,14 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_arrays] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_arrays] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute], method to send unique data for each instance to the shader.
     ///
     pub fn draw_arrays_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        primitive_type: PrimitiveType,
         count: u32,
         instance_count: u32,
     ) {
@@ -456,11 +541,14 @@ impl Program {
         self.use_program();
         unsafe {
             self.context.draw_arrays_instanced(
-                crate::context::TRIANGLES,
+                primitive_type.to_const(),
                 0,
                 count as i32,
                 instance_count as i32,
             );
+            self.context
+                .render_stats
+                .count_draw(primitive_type, count, instance_count);
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
             for location in self.attributes.values() {
@@ -477,19 +565,21 @@ impl Program {
     }
 
     ///
-    /// Draws the triangles defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
+    /// Draws the primitives of the given [PrimitiveType] (for example triangles) defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
-    /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays]. If you only want to draw a subset of the triangles in the given [ElementBuffer], see [Program::draw_subset_of_elements].
+    /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays]. If you only want to draw a subset of the primitives in the given [ElementBuffer], see [Program::draw_subset_of_elements].
     ///
     pub fn draw_elements(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        primitive_type: PrimitiveType,
         element_buffer: &ElementBuffer,
     ) {
         self.draw_subset_of_elements(
             render_states,
             viewport,
+            primitive_type,
             element_buffer,
             0,
             element_buffer.count() as u32,
@@ -497,7 +587,7 @@ impl Program {
     }
 
     ///
-    /// Draws a subset of the triangles defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
+    /// Draws a subset of the primitives of the given [PrimitiveType] (for example triangles) defined by the given [ElementBuffer] with the given render states and viewport using this shader program.
     /// Requires that all attributes and uniforms have been defined using the use_attribute and use_uniform methods.
     /// If you do not want to use an [ElementBuffer], see [Program::draw_arrays].
     ///
@@ -505,6 +595,7 @@ impl Program {
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        primitive_type: PrimitiveType,
         element_buffer: &ElementBuffer,
         first: u32,
         count: u32,
@@ -515,11 +606,14 @@ impl Program {
         element_buffer.bind();
         unsafe {
             self.context.draw_elements(
-                crate::context::TRIANGLES,
+                primitive_type.to_const(),
                 count as i32,
                 element_buffer.data_type(),
                 first as i32,
             );
+            self.context
+                .render_stats
+                .count_draw(primitive_type, count, 1);
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
 
@@ -537,19 +631,21 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_elements] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_elements] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute] method to send unique data for each instance to the shader.
     ///
     pub fn draw_elements_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        primitive_type: PrimitiveType,
         element_buffer: &ElementBuffer,
         instance_count: u32,
     ) {
         self.draw_subset_of_elements_instanced(
             render_states,
             viewport,
+            primitive_type,
             element_buffer,
             0,
             element_buffer.count() as u32,
@@ -558,13 +654,14 @@ impl Program {
     }
 
     ///
-    /// Same as [Program::draw_subset_of_elements] except it renders 'instance_count' instances of the same set of triangles.
+    /// Same as [Program::draw_subset_of_elements] except it renders 'instance_count' instances of the same set of primitives.
     /// Use the [Program::use_instance_attribute] method to send unique data for each instance to the shader.
     ///
     pub fn draw_subset_of_elements_instanced(
         &self,
         render_states: RenderStates,
         viewport: Viewport,
+        primitive_type: PrimitiveType,
         element_buffer: &ElementBuffer,
         first: u32,
         count: u32,
@@ -576,12 +673,15 @@ impl Program {
         element_buffer.bind();
         unsafe {
             self.context.draw_elements_instanced(
-                crate::context::TRIANGLES,
+                primitive_type.to_const(),
                 count as i32,
                 element_buffer.data_type(),
                 first as i32,
                 instance_count as i32,
             );
+            self.context
+                .render_stats
+                .count_draw(primitive_type, count, instance_count);
             self.context
                 .bind_buffer(crate::context::ELEMENT_ARRAY_BUFFER, None);
             for location in self.attributes.values() {
@@ -622,6 +722,7 @@ impl Program {
     }
 
     fn use_program(&self) {
+        self.context.render_stats.count_program_use(self.key);
         unsafe {
             self.context.use_program(Some(self.id));
         }
@@ -641,3 +742,39 @@ impl Drop for Program {
         }
     }
 }
+
+///
+/// Defines how the vertices are assembled into primitives in a draw call, see for example [Program::draw_arrays].
+///
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum PrimitiveType {
+    /// Each vertex is drawn as a point.
+    Points,
+    /// Each pair of vertices is drawn as a line segment.
+    Lines,
+    /// The vertices are drawn as a connected series of line segments.
+    LineStrip,
+    /// Same as [PrimitiveType::LineStrip] except that the last vertex is also connected to the first vertex.
+    LineLoop,
+    /// Each group of three vertices is drawn as a triangle.
+    #[default]
+    Triangles,
+    /// The vertices are drawn as a series of connected triangles where each vertex after the first two defines a new triangle.
+    TriangleStrip,
+    /// The vertices are drawn as a series of triangles which all share the first vertex.
+    TriangleFan,
+}
+
+impl PrimitiveType {
+    pub(in crate::core) fn to_const(self) -> u32 {
+        match self {
+            Self::Points => crate::context::POINTS,
+            Self::Lines => crate::context::LINES,
+            Self::LineStrip => crate::context::LINE_STRIP,
+            Self::LineLoop => crate::context::LINE_LOOP,
+            Self::Triangles => crate::context::TRIANGLES,
+            Self::TriangleStrip => crate::context::TRIANGLE_STRIP,
+            Self::TriangleFan => crate::context::TRIANGLE_FAN,
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use crate::core::*;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+///
+/// Stores the binaries of linked shader programs on disk and loads them again, so that a [Program] does not have to be compiled every time the application starts.
+/// Requires that the program binary functions are loaded (see [Context::load_program_binary_functions]),
+/// that the driver supports at least one program binary format and that a directory is set (see [Context::set_program_binary_directory]).
+/// Otherwise, or if loading a binary fails for any reason, the program is just compiled from source.
+///
+#[derive(Default)]
+pub(super) struct ProgramBinaryCache {
+    #[cfg(not(target_arch = "wasm32"))]
+    functions: RwLock<Option<native::ProgramBinaryFunctions>>,
+    directory: RwLock<Option<PathBuf>>,
+}
+
+impl ProgramBinaryCache {
+    pub fn set_directory(&self, directory: Option<PathBuf>) {
+        *self.directory.write().unwrap() = directory;
+    }
+
+    pub fn directory(&self) -> Option<PathBuf> {
+        self.directory.read().unwrap().clone()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub unsafe fn load_functions(
+        &self,
+        context: &crate::context::Context,
+        loader: impl FnMut(&str) -> *const std::ffi::c_void,
+    ) {
+        *self.functions.write().unwrap() = native::ProgramBinaryFunctions::load(context, loader);
+    }
+
+    pub fn is_supported(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.functions.read().unwrap().is_some()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            false
+        }
+    }
+
+    ///
+    /// Returns a linked program created from a binary stored on disk, if there is a valid binary for the given source and driver.
+    ///
+    #[allow(unused_variables)]
+    pub unsafe fn load(
+        &self,
+        context: &crate::context::Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Option<crate::context::Program> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let functions = (*self.functions.read().unwrap())?;
+            let path = self.path(context, vertex_shader_source, fragment_shader_source)?;
+            let data = std::fs::read(&path).ok()?;
+            let program = functions.program_from_binary(context, &data);
+            if program.is_none() {
+                // The binary is invalid, for example because the driver was updated, so it is removed and the program is compiled instead.
+                std::fs::remove_file(&path).ok();
+            }
+            program
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            None
+        }
+    }
+
+    ///
+    /// Stores the binary of the given linked program on disk, if possible. Failure is silently ignored.
+    ///
+    #[allow(unused_variables)]
+    pub unsafe fn store(
+        &self,
+        context: &crate::context::Context,
+        program: crate::context::Program,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(functions) = *self.functions.read().unwrap() {
+                if let Some(path) = self.path(context, vertex_shader_source, fragment_shader_source)
+                {
+                    if let Some(data) = functions.binary_from_program(program) {
+                        if let Some(parent) = path.parent() {
+                            std::fs::create_dir_all(parent).ok();
+                        }
+                        std::fs::write(path, data).ok();
+                    }
+                }
+            }
+        }
+    }
+
+    ///
+    /// Tells the driver that the binary of the given program will be retrieved, which some drivers require to return a binary at all.
+    /// Must be called before the program is linked.
+    ///
+    #[allow(unused_variables)]
+    pub unsafe fn set_retrievable_hint(&self, program: crate::context::Program) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if self.directory.read().unwrap().is_some() {
+                if let Some(functions) = *self.functions.read().unwrap() {
+                    functions.set_retrievable_hint(program);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn path(
+        &self,
+        context: &crate::context::Context,
+        vertex_shader_source: &str,
+        fragment_shader_source: &str,
+    ) -> Option<PathBuf> {
+        let directory = self.directory.read().unwrap().clone()?;
+        let key = program_binary_key(context, vertex_shader_source, fragment_shader_source);
+        Some(directory.join(format!("{:016x}.bin", key)))
+    }
+}
+
+///
+/// A hash of the full shader source and the driver. FNV-1a is used since the hash must be stable across runs and compiler versions.
+///
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn program_binary_key(
+    context: &crate::context::Context,
+    vertex_shader_source: &str,
+    fragment_shader_source: &str,
+) -> u64 {
+    let driver = format!(
+        "{}\n{}\n{}",
+        context.get_parameter_string(crate::context::VENDOR),
+        context.get_parameter_string(crate::context::RENDERER),
+        context.get_parameter_string(crate::context::VERSION)
+    );
+    let mut hash = 0xcbf29ce484222325u64;
+    for part in [
+        driver.as_str(),
+        vertex_shader_source,
+        fragment_shader_source,
+    ] {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native {
+    use crate::context::HasContext;
+    use std::ffi::c_void;
+
+    const MAGIC: &[u8; 4] = b"3dpb";
+
+    type GetProgramIv = unsafe extern "system" fn(u32, u32, *mut i32);
+    type GetProgramBinary = unsafe extern "system" fn(u32, i32, *mut i32, *mut u32, *mut c_void);
+    type ProgramBinary = unsafe extern "system" fn(u32, u32, *const c_void, i32);
+    type ProgramParameteri = unsafe extern "system" fn(u32, u32, i32);
+
+    #[derive(Clone, Copy)]
+    pub struct ProgramBinaryFunctions {
+        get_program_iv: GetProgramIv,
+        get_program_binary: GetProgramBinary,
+        program_binary: ProgramBinary,
+        program_parameter_i: Option<ProgramParameteri>,
+    }
+
+    impl ProgramBinaryFunctions {
+        pub unsafe fn load(
+            context: &crate::context::Context,
+            mut loader: impl FnMut(&str) -> *const c_void,
+        ) -> Option<Self> {
+            if context.get_parameter_i32(crate::context::NUM_PROGRAM_BINARY_FORMATS) <= 0 {
+                return None;
+            }
+            let get_program_iv = loader("glGetProgramiv");
+            let get_program_binary = loader("glGetProgramBinary");
+            let program_binary = loader("glProgramBinary");
+            let program_parameter_i = loader("glProgramParameteri");
+            if get_program_iv.is_null() || get_program_binary.is_null() || program_binary.is_null()
+            {
+                return None;
+            }
+            Some(Self {
+                get_program_iv: std::mem::transmute::<*const c_void, GetProgramIv>(get_program_iv),
+                get_program_binary: std::mem::transmute::<*const c_void, GetProgramBinary>(
+                    get_program_binary,
+                ),
+                program_binary: std::mem::transmute::<*const c_void, ProgramBinary>(program_binary),
+                program_parameter_i: (!program_parameter_i.is_null()).then(|| {
+                    std::mem::transmute::<*const c_void, ProgramParameteri>(program_parameter_i)
+                }),
+            })
+        }
+
+        pub unsafe fn set_retrievable_hint(&self, program: crate::context::Program) {
+            if let Some(program_parameter_i) = self.program_parameter_i {
+                program_parameter_i(
+                    program.0.get(),
+                    crate::context::PROGRAM_BINARY_RETRIEVABLE_HINT,
+                    1,
+                );
+            }
+        }
+
+        pub unsafe fn binary_from_program(
+            &self,
+            program: crate::context::Program,
+        ) -> Option<Vec<u8>> {
+            let mut length = 0;
+            (self.get_program_iv)(
+                program.0.get(),
+                crate::context::PROGRAM_BINARY_LENGTH,
+                &mut length,
+            );
+            if length <= 0 {
+                return None;
+            }
+            let mut binary = vec![0u8; length as usize];
+            let mut written = 0;
+            let mut format = 0;
+            (self.get_program_binary)(
+                program.0.get(),
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr() as *mut c_void,
+            );
+            if written <= 0 {
+                return None;
+            }
+            binary.truncate(written as usize);
+            let mut data = MAGIC.to_vec();
+            data.extend(format.to_le_bytes());
+            data.extend(binary);
+            Some(data)
+        }
+
+        pub unsafe fn program_from_binary(
+            &self,
+            context: &crate::context::Context,
+            data: &[u8],
+        ) -> Option<crate::context::Program> {
+            if data.len() <= 8 || &data[0..4] != MAGIC {
+                return None;
+            }
+            let format = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+            let binary = &data[8..];
+            let program = context.create_program().ok()?;
+            // Clear any pending error, so that only an error caused by this call is cleared below
+            context.get_error();
+            (self.program_binary)(
+                program.0.get(),
+                format,
+                binary.as_ptr() as *const c_void,
+                binary.len() as i32,
+            );
+            // Clear any error caused by an unsupported format
+            context.get_error();
+            if context.get_program_link_status(program) {
+                Some(program)
+            } else {
+                context.delete_program(program);
+                None
+            }
+        }
+    }
+}
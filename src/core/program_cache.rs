@@ -0,0 +1,179 @@
+use crate::core::*;
+use std::collections::HashMap;
+
+///
+/// A cache of compiled [Program]s, stored in the [Context], to avoid recompiling a [Program] every frame.
+/// The programs are identified by a key which is typically constructed from the ids of the geometry, material/effect and lights used to generate the shader source.
+///
+/// The cache is unbounded by default, use [Context::set_program_cache_capacity] to limit the number of programs in the cache of a [Context].
+/// When the capacity is exceeded, the least recently used program is evicted.
+///
+pub struct ProgramCache {
+    programs: HashMap<Vec<u8>, (Program, u64)>,
+    capacity: Option<usize>,
+    time: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl ProgramCache {
+    pub(super) fn new() -> Self {
+        Self {
+            programs: HashMap::new(),
+            capacity: None,
+            time: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    ///
+    /// Returns the program with the given key if it is in the cache, otherwise it is created using the `create` callback, inserted into the cache and returned.
+    /// Counts as a hit if the program was in the cache and a miss otherwise.
+    ///
+    pub fn get_or_insert_with(
+        &mut self,
+        key: Vec<u8>,
+        create: impl FnOnce() -> Program,
+    ) -> &Program {
+        match self.get_or_try_insert_with(key, || Ok::<_, std::convert::Infallible>(create())) {
+            Ok(program) => program,
+            Err(e) => match e {},
+        }
+    }
+
+    ///
+    /// Returns the program with the given key if it is in the cache, otherwise it is created using the `create` callback, inserted into the cache and returned.
+    /// If the `create` callback returns an error, nothing is inserted into the cache and the error is returned.
+    /// Counts as a hit if the program was in the cache and a miss otherwise.
+    ///
+    pub fn get_or_try_insert_with<E>(
+        &mut self,
+        key: Vec<u8>,
+        create: impl FnOnce() -> Result<Program, E>,
+    ) -> Result<&Program, E> {
+        self.time += 1;
+        if self.programs.contains_key(&key) {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+            let program = create()?;
+            self.insert_and_evict(key.clone(), program);
+        }
+        let time = self.time;
+        let entry = self.programs.get_mut(&key).unwrap();
+        entry.1 = time;
+        Ok(&entry.0)
+    }
+
+    ///
+    /// Inserts the program with the given key into the cache, replacing any existing program with the same key.
+    /// This does not count as a hit or miss.
+    ///
+    pub fn insert(&mut self, key: Vec<u8>, program: Program) {
+        self.time += 1;
+        self.insert_and_evict(key, program);
+    }
+
+    ///
+    /// Returns whether or not a program with the given key is in the cache.
+    /// This does not count as a hit or miss.
+    ///
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.programs.contains_key(key)
+    }
+
+    ///
+    /// Removes the program with the given key from the cache and returns it, if it was in the cache.
+    ///
+    pub fn remove(&mut self, key: &[u8]) -> Option<Program> {
+        self.programs.remove(key).map(|(program, _)| program)
+    }
+
+    ///
+    /// Removes all programs from the cache. The hit and miss counts are not reset, use [ProgramCache::reset_statistics] for that.
+    ///
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+
+    ///
+    /// Returns the number of programs in the cache.
+    ///
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    ///
+    /// Returns whether or not the cache is empty.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    ///
+    /// Returns the keys of the programs in the cache in arbitrary order.
+    ///
+    pub fn keys(&self) -> impl Iterator<Item = &[u8]> {
+        self.programs.keys().map(|key| key.as_slice())
+    }
+
+    ///
+    /// Returns the number of times a requested program was found in the cache since the cache was created or the statistics was reset.
+    ///
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    ///
+    /// Returns the number of times a requested program was not found in the cache and therefore had to be compiled
+    /// since the cache was created or the statistics was reset.
+    ///
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    ///
+    /// Resets the hit and miss counts.
+    ///
+    pub fn reset_statistics(&mut self) {
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    ///
+    /// Returns the maximum number of programs in the cache or `None` if the cache is unbounded.
+    ///
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    ///
+    /// Sets the maximum number of programs in the cache, `None` means that the cache is unbounded.
+    /// If the cache contains more programs than the new capacity, the least recently used programs are evicted.
+    /// The capacity is at least one.
+    ///
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity.map(|c| c.max(1));
+        self.evict();
+    }
+
+    fn insert_and_evict(&mut self, key: Vec<u8>, program: Program) {
+        self.programs.insert(key, (program, self.time));
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        if let Some(capacity) = self.capacity {
+            while self.programs.len() > capacity {
+                let key = self
+                    .programs
+                    .iter()
+                    .min_by_key(|(_, (_, time))| *time)
+                    .map(|(key, _)| key.clone())
+                    .unwrap();
+                self.programs.remove(&key);
+            }
+        }
+    }
+}
@@ -31,6 +31,20 @@ pub struct RenderStates {
     /// Defines whether the triangles that are backfacing, frontfacing or both should be skipped in a render call.
     ///
     pub cull: Cull,
+
+    ///
+    /// Defines the stencil test and the stencil operations in a render call.
+    /// The stencil test determines whether or not a fragment from the current render call should be discarded
+    /// when comparing a reference value with the value in the stencil buffer.
+    ///
+    pub stencil: Stencil,
+
+    ///
+    /// Defines the depth bias (polygon offset) in a render call.
+    /// The depth bias is added to the depth of each fragment before the depth test and before it is written to the depth buffer.
+    /// This is usually used to avoid z-fighting between coplanar geometries, for example decals or a wireframe drawn on top of a mesh.
+    ///
+    pub depth_bias: DepthBias,
 }
 
 ///
@@ -79,6 +93,39 @@ impl Default for DepthTest {
     }
 }
 
+///
+/// Defines the depth bias (polygon offset) which is added to the depth of each fragment in a render call.
+/// The offset is `factor * slope + units * r` where `slope` is the maximum depth slope of the triangle and `r` is the smallest resolvable difference in the depth buffer.
+/// A positive offset pushes the fragments away from the camera and a negative offset pulls them towards the camera.
+/// The depth bias is disabled if both the factor and the units are zero.
+///
+/// **Note:** The depth bias only applies to triangles, not to lines and points.
+///
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct DepthBias {
+    /// Scales the maximum depth slope of the triangle.
+    pub factor: f32,
+    /// Scales the smallest resolvable difference in the depth buffer.
+    pub units: f32,
+}
+
+impl DepthBias {
+    ///
+    /// No depth bias.
+    ///
+    pub const NONE: Self = Self {
+        factor: 0.0,
+        units: 0.0,
+    };
+
+    ///
+    /// Constructs a new depth bias with the given factor and units.
+    ///
+    pub const fn new(factor: f32, units: f32) -> Self {
+        Self { factor, units }
+    }
+}
+
 ///
 /// Defines which channels (red, green, blue, alpha and depth) to write to in a render call.
 ///
@@ -240,3 +287,99 @@ pub enum BlendEquationType {
     Max,
     Min,
 }
+
+///
+/// Defines the stencil test and the stencil operations for front- and backfacing triangles in a render call.
+///
+/// **Note:** The stencil test always passes if the render call is not writing to a render target with a stencil buffer,
+/// for example a [DepthTexture2D](crate::core::DepthTexture2D) created with the [DepthStencil](crate::core::DepthStencil) data type.
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum Stencil {
+    Enabled {
+        front: StencilFace,
+        back: StencilFace,
+    },
+    #[default]
+    Disabled,
+}
+
+impl Stencil {
+    ///
+    /// Enables the stencil test with the same settings for both front- and backfacing triangles.
+    ///
+    pub const fn front_and_back(face: StencilFace) -> Self {
+        Self::Enabled {
+            front: face,
+            back: face,
+        }
+    }
+}
+
+///
+/// The stencil test and stencil operations for either front- or backfacing triangles, see [Stencil].
+///
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct StencilFace {
+    /// The function used to compare the reference value with the value in the stencil buffer.
+    pub function: StencilFunction,
+    /// The reference value used in the stencil test and written to the stencil buffer by [StencilOperation::Replace].
+    pub reference: u32,
+    /// The mask that is applied to both the reference value and the value in the stencil buffer before they are compared.
+    pub read_mask: u32,
+    /// The mask that defines which bits of the stencil buffer are written to.
+    pub write_mask: u32,
+    /// The operation applied to the stencil buffer when the stencil test fails.
+    pub fail: StencilOperation,
+    /// The operation applied to the stencil buffer when the stencil test passes but the depth test fails.
+    pub depth_fail: StencilOperation,
+    /// The operation applied to the stencil buffer when both the stencil test and the depth test passes.
+    pub pass: StencilOperation,
+}
+
+impl Default for StencilFace {
+    fn default() -> Self {
+        Self {
+            function: StencilFunction::Always,
+            reference: 0,
+            read_mask: u32::MAX,
+            write_mask: u32::MAX,
+            fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+///
+/// The function used to compare the reference value with the value in the stencil buffer in the stencil test, see [StencilFace].
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilFunction {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+///
+/// The operation applied to the value in the stencil buffer, see [StencilFace].
+///
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    Replace,
+    Increment,
+    IncrementWrap,
+    Decrement,
+    DecrementWrap,
+    Invert,
+}
@@ -0,0 +1,106 @@
+use crate::core::*;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::RwLock;
+
+///
+/// Statistics about the rendering submitted to the GPU since the statistics was last reset (see [Context::render_stats]).
+/// Useful for finding scenes with an unexpectedly high number of draw calls, for example when many [Program::draw_arrays] calls could be replaced by a single [Program::draw_arrays_instanced] call.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of draw calls, ie. calls to one of the draw methods on [Program].
+    pub draw_calls: u32,
+    /// The number of triangles drawn, including the triangles in all instances. Points and lines are not counted.
+    pub triangles: u64,
+    /// The number of instances drawn. A draw call which is not instanced counts as one instance.
+    pub instances: u64,
+    /// The number of times a different [Program] was used than the previously used [Program].
+    pub program_switches: u32,
+    /// The number of times a texture was bound to a [Program].
+    pub texture_binds: u32,
+    /// The number of programs compiled, ie. programs created using [Program::from_source] which were not loaded from a program binary.
+    pub programs_compiled: u32,
+}
+
+///
+/// Counts the [RenderStats] using atomic counters, so that counting does not require locking on every draw call, program use and texture bind.
+///
+#[derive(Default)]
+pub(super) struct RenderStatsCounter {
+    draw_calls: AtomicU32,
+    triangles: AtomicU64,
+    instances: AtomicU64,
+    program_switches: AtomicU32,
+    texture_binds: AtomicU32,
+    programs_compiled: AtomicU32,
+    last_program: AtomicU32,
+    previous: RwLock<RenderStats>,
+}
+
+impl RenderStatsCounter {
+    pub fn current(&self) -> RenderStats {
+        RenderStats {
+            draw_calls: self.draw_calls.load(Ordering::Relaxed),
+            triangles: self.triangles.load(Ordering::Relaxed),
+            instances: self.instances.load(Ordering::Relaxed),
+            program_switches: self.program_switches.load(Ordering::Relaxed),
+            texture_binds: self.texture_binds.load(Ordering::Relaxed),
+            programs_compiled: self.programs_compiled.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn previous(&self) -> RenderStats {
+        *self.previous.read().unwrap()
+    }
+
+    pub fn reset(&self) -> RenderStats {
+        let stats = RenderStats {
+            draw_calls: self.draw_calls.swap(0, Ordering::Relaxed),
+            triangles: self.triangles.swap(0, Ordering::Relaxed),
+            instances: self.instances.swap(0, Ordering::Relaxed),
+            program_switches: self.program_switches.swap(0, Ordering::Relaxed),
+            texture_binds: self.texture_binds.swap(0, Ordering::Relaxed),
+            programs_compiled: self.programs_compiled.swap(0, Ordering::Relaxed),
+        };
+        *self.previous.write().unwrap() = stats;
+        stats
+    }
+
+    pub fn count_draw(&self, primitive_type: PrimitiveType, count: u32, instance_count: u32) {
+        let triangles = match primitive_type {
+            PrimitiveType::Triangles => count / 3,
+            PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan => count.saturating_sub(2),
+            _ => 0,
+        };
+        self.draw_calls.fetch_add(1, Ordering::Relaxed);
+        self.triangles
+            .fetch_add(triangles as u64 * instance_count as u64, Ordering::Relaxed);
+        self.instances
+            .fetch_add(instance_count as u64, Ordering::Relaxed);
+    }
+
+    ///
+    /// Counts a program switch if the program with the given key, see [next_program_key], is different from the previously used program.
+    ///
+    pub fn count_program_use(&self, program_key: u32) {
+        if self.last_program.swap(program_key, Ordering::Relaxed) != program_key {
+            self.program_switches.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn count_texture_bind(&self) {
+        self.texture_binds.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count_program_compiled(&self) {
+        self.programs_compiled.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+///
+/// Returns a new unique non-zero key used to identify a [Program] when counting program switches.
+///
+pub(super) fn next_program_key() -> u32 {
+    static NEXT_PROGRAM_KEY: AtomicU32 = AtomicU32::new(1);
+    NEXT_PROGRAM_KEY.fetch_add(1, Ordering::Relaxed)
+}
@@ -42,6 +42,8 @@ pub struct RenderTarget<'a> {
     pub(crate) context: Context,
     width: u32,
     height: u32,
+    order_independent_transparency: bool,
+    depth_pre_pass: bool,
 }
 
 impl<'a> RenderTarget<'a> {
@@ -57,6 +59,8 @@ impl<'a> RenderTarget<'a> {
             depth: None,
             width,
             height,
+            order_independent_transparency: false,
+            depth_pre_pass: false,
         }
     }
 
@@ -73,6 +77,8 @@ impl<'a> RenderTarget<'a> {
             depth: Some(depth),
             width,
             height,
+            order_independent_transparency: false,
+            depth_pre_pass: false,
         }
     }
 
@@ -86,6 +92,49 @@ impl<'a> RenderTarget<'a> {
         self.height
     }
 
+    ///
+    /// Returns the depth texture of this render target or `None` if it does not have one, for example if it is the screen.
+    ///
+    pub(crate) fn depth_texture(&self) -> Option<DepthTexture<'a>> {
+        self.depth.as_ref().and_then(|depth| depth.texture())
+    }
+
+    ///
+    /// Enables or disables weighted blended order independent transparency when rendering objects with a transparent material into this target,
+    /// see [RenderTarget::render](crate::renderer::RenderTarget::render).
+    /// This is disabled by default, in which case transparent objects are sorted and blended back to front.
+    ///
+    pub fn set_order_independent_transparency(&mut self, enabled: bool) {
+        self.order_independent_transparency = enabled;
+    }
+
+    ///
+    /// Returns whether or not order independent transparency is enabled, see [RenderTarget::set_order_independent_transparency].
+    ///
+    pub fn order_independent_transparency(&self) -> bool {
+        self.order_independent_transparency
+    }
+
+    ///
+    /// Enables or disables a depth pre-pass when rendering objects with an opaque forward material into this target,
+    /// see [RenderTarget::render](crate::renderer::RenderTarget::render).
+    /// The depth of the objects is rendered first with a depth material using the cull and depth test of their materials, and the objects are then shaded using a [DepthTest::LessOrEqual] depth test without writing depth,
+    /// so that each pixel is only shaded once. Objects whose material does not write depth or which do not expose their material (see [Object::use_material](crate::renderer::Object::use_material)) are rendered as usual.
+    /// Materials which discard fragments should not be used with a depth pre-pass, since the discarded fragments are still written in the depth pre-pass. This reduces the cost of overdraw when the materials are expensive, for example a physical material with many lights,
+    /// at the cost of rendering the geometry twice.
+    /// This is disabled by default.
+    ///
+    pub fn set_depth_pre_pass(&mut self, enabled: bool) {
+        self.depth_pre_pass = enabled;
+    }
+
+    ///
+    /// Returns whether or not the depth pre-pass is enabled, see [RenderTarget::set_depth_pre_pass].
+    ///
+    pub fn depth_pre_pass(&self) -> bool {
+        self.depth_pre_pass
+    }
+
     ///
     /// Clears the color and depth of this render target as defined by the given clear state.
     ///
@@ -114,6 +163,7 @@ impl<'a> RenderTarget<'a> {
     /// Writes whatever rendered in the `render` closure into the part of this render target defined by the scissor box.
     ///
     pub fn write_partially(&self, scissor_box: ScissorBox, render: impl FnOnce()) -> &Self {
+        self.context.invalidate_render_states();
         self.context.set_scissor(scissor_box);
         self.bind(crate::context::DRAW_FRAMEBUFFER);
         render();
@@ -244,7 +294,7 @@ impl<'a> RenderTarget<'a> {
                     .to_le_bytes(),
             );
             let mut programs = self.context.programs.write().unwrap();
-            let program = programs.entry(id).or_insert_with(|| {
+            let program = programs.get_or_insert_with(id, || {
                 let fragment_shader_source = format!(
                     "{}\n{}\n
                     in vec2 uvs;
@@ -308,7 +358,7 @@ impl<'a> RenderTarget<'a> {
             let mut id = full_screen_id().to_le_bytes().to_vec();
             id.extend((0b1u16 << 13 | 0b1u16 << 11 | color_texture.id()).to_le_bytes());
             let mut programs = self.context.programs.write().unwrap();
-            let program = programs.entry(id).or_insert_with(|| {
+            let program = programs.get_or_insert_with(id, || {
                 let fragment_shader_source = format!(
                     "{}\nin vec2 uvs;
                     layout (location = 0) out vec4 color;
@@ -362,7 +412,7 @@ impl<'a> RenderTarget<'a> {
             let mut id = full_screen_id().to_le_bytes().to_vec();
             id.extend((0b1u16 << 13 | 0b1u16 << 10 | depth_texture.id()).to_le_bytes());
             let mut programs = self.context.programs.write().unwrap();
-            let program = programs.entry(id).or_insert_with(|| {
+            let program = programs.get_or_insert_with(id, || {
                 let fragment_shader_source = format!(
                     "{}\n
                         in vec2 uvs;
@@ -411,6 +461,8 @@ impl<'a> RenderTarget<'a> {
             context: context.clone(),
             width,
             height,
+            order_independent_transparency: false,
+            depth_pre_pass: false,
         }
     }
 
@@ -466,6 +518,8 @@ impl<'a> RenderTarget<'a> {
             depth: None,
             width,
             height,
+            order_independent_transparency: false,
+            depth_pre_pass: false,
         }
     }
 
@@ -479,6 +533,8 @@ impl<'a> RenderTarget<'a> {
             color: None,
             width,
             height,
+            order_independent_transparency: false,
+            depth_pre_pass: false,
         }
     }
 
@@ -0,0 +1,292 @@
+use crate::core::*;
+use std::collections::HashMap;
+
+///
+/// A collection of named pieces of shader source, called chunks, which can be included in the shader source given to [Program::from_source] using an `#include "name"` directive on a separate line.
+/// Each chunk is only included once in a shader, even if it is included multiple times, and a chunk can include other chunks.
+/// When a shader fails to compile, the line numbers in the compile error are mapped back to the chunk and the line in that chunk.
+///
+/// The chunk `shared.frag`, which contains common functionality like color space conversions,
+/// and the chunk `light_shared.frag`, which contains the lighting calculations, are always available and cannot be replaced or removed, since the built-in shaders depend on them.
+/// Use [ShaderChunks::register] to add custom chunks, for example to share functionality between custom materials.
+///
+/// **Note:** Programs in the program cache are not recompiled if a chunk changes, so clear the cache if needed (see [Context::clear_program_cache]).
+///
+pub struct ShaderChunks {
+    chunks: HashMap<String, String>,
+}
+
+/// The names of the built-in chunks which cannot be replaced or removed.
+const RESERVED_CHUNKS: [&str; 2] = ["shared.frag", "light_shared.frag"];
+
+impl ShaderChunks {
+    pub(super) fn new() -> Self {
+        let mut chunks = HashMap::new();
+        chunks.insert(
+            RESERVED_CHUNKS[0].to_string(),
+            include_str!("shared.frag").to_string(),
+        );
+        chunks.insert(
+            RESERVED_CHUNKS[1].to_string(),
+            include_str!("../renderer/light/shaders/light_shared.frag").to_string(),
+        );
+        Self { chunks }
+    }
+
+    ///
+    /// Registers the given source as a chunk with the given name, replacing any existing chunk with the same name.
+    /// Returns an error if the name is the name of a built-in chunk.
+    ///
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> Result<(), CoreError> {
+        let name = name.into();
+        if Self::is_reserved(&name) {
+            return Err(CoreError::ReservedShaderChunk(name));
+        }
+        self.chunks.insert(name, source.into());
+        Ok(())
+    }
+
+    ///
+    /// Removes the chunk with the given name and returns the source of it, if it exists.
+    /// Returns an error if the name is the name of a built-in chunk.
+    ///
+    pub fn remove(&mut self, name: &str) -> Result<Option<String>, CoreError> {
+        if Self::is_reserved(name) {
+            return Err(CoreError::ReservedShaderChunk(name.to_string()));
+        }
+        Ok(self.chunks.remove(name))
+    }
+
+    ///
+    /// Returns whether or not the chunk with the given name is a built-in chunk, which cannot be replaced or removed.
+    ///
+    pub fn is_reserved(name: &str) -> bool {
+        RESERVED_CHUNKS.contains(&name)
+    }
+
+    ///
+    /// Returns the source of the chunk with the given name, if it exists.
+    ///
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.chunks.get(name).map(|s| s.as_str())
+    }
+
+    ///
+    /// Returns whether or not a chunk with the given name exists.
+    ///
+    pub fn contains(&self, name: &str) -> bool {
+        self.chunks.contains_key(name)
+    }
+
+    ///
+    /// Returns the names of all chunks in arbitrary order.
+    ///
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.chunks.keys().map(|s| s.as_str())
+    }
+
+    ///
+    /// Returns the given source, where the `#include` directives are replaced by the source of the included chunks, together with a map from the lines in the output to the origin of those lines.
+    /// The given header is placed before the source.
+    ///
+    pub(super) fn preprocess(
+        &self,
+        header: &str,
+        name: &str,
+        source: &str,
+    ) -> Result<(String, SourceMap), CoreError> {
+        let mut output = String::new();
+        let mut map = SourceMap { lines: Vec::new() };
+        for (i, line) in header.lines().enumerate() {
+            output.push_str(line);
+            output.push('\n');
+            map.lines.push(("header".to_string(), i as u32 + 1));
+        }
+        let mut included = Vec::new();
+        self.expand(name, source, &mut included, &mut output, &mut map)?;
+        Ok((output, map))
+    }
+
+    fn expand(
+        &self,
+        name: &str,
+        source: &str,
+        included: &mut Vec<String>,
+        output: &mut String,
+        map: &mut SourceMap,
+    ) -> Result<(), CoreError> {
+        for (i, line) in source.lines().enumerate() {
+            if let Some(chunk) = include_directive(line) {
+                if !included.iter().any(|c| c == chunk) {
+                    let chunk_source = self
+                        .get(chunk)
+                        .ok_or_else(|| CoreError::MissingShaderChunk(chunk.to_string()))?;
+                    included.push(chunk.to_string());
+                    self.expand(chunk, chunk_source, included, output, map)?;
+                }
+            } else {
+                output.push_str(line);
+                output.push('\n');
+                map.lines.push((name.to_string(), i as u32 + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn include_directive(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix('#')?.trim_start();
+    let rest = rest.strip_prefix("include")?.trim();
+    rest.strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')))
+}
+
+///
+/// Maps each line in a preprocessed shader source to the chunk and line it originates from.
+///
+pub(super) struct SourceMap {
+    lines: Vec<(String, u32)>,
+}
+
+impl SourceMap {
+    ///
+    /// Appends the origin to each line in the given compile log which refers to a line in the preprocessed source.
+    /// Recognizes the `0:12`, `0(12)` and `ERROR: 0:12:` formats used by the common drivers.
+    ///
+    pub fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| {
+                match log_line_number(line)
+                    .and_then(|n| self.lines.get((n as usize).checked_sub(1)?))
+                {
+                    Some((chunk, n)) => format!("{} [{}:{}]", line, chunk, n),
+                    None => line.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn log_line_number(line: &str) -> Option<u32> {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii_digit() && (i == 0 || !bytes[i - 1].is_ascii_alphanumeric()) {
+            let mut j = i;
+            while j < bytes.len() && bytes[j].is_ascii_digit() {
+                j += 1;
+            }
+            if j + 1 < bytes.len() && (bytes[j] == b':' || bytes[j] == b'(') {
+                let mut k = j + 1;
+                while k < bytes.len() && bytes[k].is_ascii_digit() {
+                    k += 1;
+                }
+                if k > j + 1 {
+                    return line[j + 1..k].parse().ok();
+                }
+            }
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn include_directive_formats() {
+        assert_eq!(include_directive("#include \"a.frag\""), Some("a.frag"));
+        assert_eq!(
+            include_directive("  #  include  <b.frag>  "),
+            Some("b.frag")
+        );
+        assert_eq!(include_directive("#include \"a.frag"), None);
+        assert_eq!(include_directive("#define INCLUDE 1"), None);
+        assert_eq!(include_directive("// #include \"a.frag\""), None);
+    }
+
+    #[test]
+    fn log_line_number_formats() {
+        // Mesa and Apple
+        assert_eq!(
+            log_line_number("0:12(5): error: undeclared identifier"),
+            Some(12)
+        );
+        // Nvidia
+        assert_eq!(
+            log_line_number("0(12) : error C1008: undefined variable"),
+            Some(12)
+        );
+        // ANGLE and AMD
+        assert_eq!(
+            log_line_number("ERROR: 0:12: 'x' : undeclared identifier"),
+            Some(12)
+        );
+        assert_eq!(log_line_number("error: vec3 x0:12"), None);
+        assert_eq!(log_line_number("no line number"), None);
+    }
+
+    #[test]
+    fn reserved_chunks() {
+        let mut chunks = ShaderChunks::new();
+        assert!(chunks.contains("shared.frag"));
+        assert!(chunks.contains("light_shared.frag"));
+        assert!(chunks.register("shared.frag", "").is_err());
+        assert!(chunks.remove("light_shared.frag").is_err());
+        assert!(chunks.register("custom.frag", "void f() {}").is_ok());
+        assert_eq!(
+            chunks.remove("custom.frag").unwrap().as_deref(),
+            Some("void f() {}")
+        );
+    }
+
+    #[test]
+    fn preprocess_nested_and_deduplicated() {
+        let mut chunks = ShaderChunks::new();
+        chunks.register("a", "float a;").unwrap();
+        chunks.register("b", "#include \"a\"\nfloat b;").unwrap();
+        let (output, map) = chunks
+            .preprocess(
+                "#version 300 es",
+                "main",
+                "#include \"b\"\n#include \"a\"\nvoid main() {}",
+            )
+            .unwrap();
+        assert_eq!(
+            output,
+            "#version 300 es\nfloat a;\nfloat b;\nvoid main() {}\n"
+        );
+        assert_eq!(
+            map.lines,
+            vec![
+                ("header".to_string(), 1),
+                ("a".to_string(), 1),
+                ("b".to_string(), 2),
+                ("main".to_string(), 3),
+            ]
+        );
+        assert_eq!(
+            map.map_log("ERROR: 0:3: 'b' : redefinition"),
+            "ERROR: 0:3: 'b' : redefinition [b:2]"
+        );
+    }
+
+    #[test]
+    fn preprocess_missing_chunk() {
+        let chunks = ShaderChunks::new();
+        assert!(matches!(
+            chunks.preprocess("", "main", "#include \"missing\""),
+            Err(CoreError::MissingShaderChunk(name)) if name == "missing"
+        ));
+    }
+}
@@ -68,9 +68,17 @@ pub trait DepthTextureDataType: DepthDataType {}
 #[derive(Clone, Copy, Default, Debug)]
 pub struct f24 {}
 
+///
+/// 24 bit depth together with an 8 bit stencil which can be used as [DepthTextureDataType].
+/// Use this to create a depth texture with a stencil buffer, which is needed when rendering with a [Stencil] test.
+///
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DepthStencil {}
+
 impl DepthTextureDataType for f16 {}
 impl DepthTextureDataType for f24 {}
 impl DepthTextureDataType for f32 {}
+impl DepthTextureDataType for DepthStencil {}
 
 ///
 /// A reference to some type of texture containing colors.
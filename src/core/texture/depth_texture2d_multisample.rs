@@ -3,6 +3,7 @@ use crate::core::texture::*;
 pub struct DepthTexture2DMultisample {
     context: Context,
     id: crate::context::Renderbuffer,
+    attachment: u32,
     width: u32,
     height: u32,
     number_of_samples: u32,
@@ -23,6 +24,7 @@ impl DepthTexture2DMultisample {
         let texture = Self {
             context: context.clone(),
             id,
+            attachment: T::attachment(),
             width,
             height,
             number_of_samples,
@@ -60,7 +62,7 @@ impl DepthTexture2DMultisample {
         unsafe {
             self.context.framebuffer_renderbuffer(
                 crate::context::FRAMEBUFFER,
-                crate::context::DEPTH_ATTACHMENT,
+                self.attachment,
                 crate::context::RENDERBUFFER,
                 Some(self.id),
             );
@@ -0,0 +1,225 @@
+use crate::core::*;
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TexturePoolKind {
+    Texture2D,
+    Texture2DArray,
+    DepthTexture2D,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct TexturePoolKey {
+    kind: TexturePoolKind,
+    internal_format: u32,
+    width: u32,
+    height: u32,
+    depth: u32,
+    min_filter: Option<Interpolation>,
+    mag_filter: Option<Interpolation>,
+    mip_map_filter: Option<Interpolation>,
+    wrap_s: Wrapping,
+    wrap_t: Wrapping,
+}
+
+enum PooledTexture {
+    Texture2D(Texture2D),
+    Texture2DArray(Texture2DArray),
+    DepthTexture2D(DepthTexture2D),
+}
+
+///
+/// A pool of textures which are only needed for a short while, for example intermediate render targets, so that they can be reused instead of allocated every frame.
+///
+#[derive(Default)]
+pub(super) struct TexturePool {
+    // The available textures and whether or not they have been used since the last trim
+    textures: Vec<(TexturePoolKey, PooledTexture, bool)>,
+}
+
+impl TexturePool {
+    fn take(&mut self, key: &TexturePoolKey) -> Option<PooledTexture> {
+        let index = self.textures.iter().position(|(k, _, _)| k == key)?;
+        Some(self.textures.swap_remove(index).1)
+    }
+
+    fn give_back(&mut self, key: TexturePoolKey, texture: PooledTexture) {
+        self.textures.push((key, texture, true));
+    }
+
+    pub fn trim(&mut self) {
+        self.textures.retain(|(_, _, used)| *used);
+        self.textures
+            .iter_mut()
+            .for_each(|(_, _, used)| *used = false);
+    }
+
+    pub fn clear(&mut self) {
+        self.textures.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+}
+
+///
+/// A texture borrowed from the texture pool in the [Context], see for example [Context::transient_texture_2d].
+/// Dereferences to the texture and returns the texture to the pool when dropped, so that it can be reused.
+/// The content of the texture is undefined when it is borrowed, so it should be cleared or completely overwritten before it is read.
+///
+pub struct TransientTexture<T> {
+    texture: Option<T>,
+    key: TexturePoolKey,
+    pool: Arc<RwLock<TexturePool>>,
+    into_pooled: fn(T) -> PooledTexture,
+}
+
+impl<T> std::ops::Deref for TransientTexture<T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.texture.as_ref().unwrap()
+    }
+}
+
+impl<T> std::ops::DerefMut for TransientTexture<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.texture.as_mut().unwrap()
+    }
+}
+
+impl<T> Drop for TransientTexture<T> {
+    fn drop(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            self.pool
+                .write()
+                .unwrap()
+                .give_back(self.key, (self.into_pooled)(texture));
+        }
+    }
+}
+
+impl TransientTexture<Texture2D> {
+    pub(super) fn new_texture_2d<T: TextureDataType>(
+        context: &Context,
+        width: u32,
+        height: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> Self {
+        let key = TexturePoolKey {
+            kind: TexturePoolKind::Texture2D,
+            internal_format: T::internal_format(),
+            width,
+            height,
+            depth: 1,
+            min_filter: Some(min_filter),
+            mag_filter: Some(mag_filter),
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+        };
+        let texture = match context.texture_pool.write().unwrap().take(&key) {
+            Some(PooledTexture::Texture2D(texture)) => texture,
+            _ => Texture2D::new_empty::<T>(
+                context,
+                width,
+                height,
+                min_filter,
+                mag_filter,
+                mip_map_filter,
+                wrap_s,
+                wrap_t,
+            ),
+        };
+        Self {
+            texture: Some(texture),
+            key,
+            pool: context.texture_pool.clone(),
+            into_pooled: PooledTexture::Texture2D,
+        }
+    }
+}
+
+impl TransientTexture<Texture2DArray> {
+    pub(super) fn new_texture_2d_array<T: TextureDataType>(
+        context: &Context,
+        width: u32,
+        height: u32,
+        depth: u32,
+        min_filter: Interpolation,
+        mag_filter: Interpolation,
+        mip_map_filter: Option<Interpolation>,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> Self {
+        let key = TexturePoolKey {
+            kind: TexturePoolKind::Texture2DArray,
+            internal_format: T::internal_format(),
+            width,
+            height,
+            depth,
+            min_filter: Some(min_filter),
+            mag_filter: Some(mag_filter),
+            mip_map_filter,
+            wrap_s,
+            wrap_t,
+        };
+        let texture = match context.texture_pool.write().unwrap().take(&key) {
+            Some(PooledTexture::Texture2DArray(texture)) => texture,
+            _ => Texture2DArray::new_empty::<T>(
+                context,
+                width,
+                height,
+                depth,
+                min_filter,
+                mag_filter,
+                mip_map_filter,
+                wrap_s,
+                wrap_t,
+            ),
+        };
+        Self {
+            texture: Some(texture),
+            key,
+            pool: context.texture_pool.clone(),
+            into_pooled: PooledTexture::Texture2DArray,
+        }
+    }
+}
+
+impl TransientTexture<DepthTexture2D> {
+    pub(super) fn new_depth_texture_2d<T: DepthTextureDataType>(
+        context: &Context,
+        width: u32,
+        height: u32,
+        wrap_s: Wrapping,
+        wrap_t: Wrapping,
+    ) -> Self {
+        let key = TexturePoolKey {
+            kind: TexturePoolKind::DepthTexture2D,
+            internal_format: T::internal_format(),
+            width,
+            height,
+            depth: 1,
+            min_filter: None,
+            mag_filter: None,
+            mip_map_filter: None,
+            wrap_s,
+            wrap_t,
+        };
+        let texture = match context.texture_pool.write().unwrap().take(&key) {
+            Some(PooledTexture::DepthTexture2D(texture)) => texture,
+            _ => DepthTexture2D::new::<T>(context, width, height, wrap_s, wrap_t),
+        };
+        Self {
+            texture: Some(texture),
+            key,
+            pool: context.texture_pool.clone(),
+            into_pooled: PooledTexture::DepthTexture2D,
+        }
+    }
+}
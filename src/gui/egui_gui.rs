@@ -15,6 +15,8 @@ pub struct GUI {
     output: RefCell<Option<egui::FullOutput>>,
     viewport: Viewport,
     modifiers: Modifiers,
+    context: Option<Context>,
+    render_stats_overlay: bool,
 }
 
 impl GUI {
@@ -23,7 +25,9 @@ impl GUI {
     ///
     pub fn new(context: &Context) -> Self {
         use std::ops::Deref;
-        Self::from_gl_context(context.deref().clone())
+        let mut gui = Self::from_gl_context(context.deref().clone());
+        gui.context = Some(context.clone());
+        gui
     }
 
     ///
@@ -36,9 +40,19 @@ impl GUI {
             output: RefCell::new(None),
             viewport: Viewport::new_at_origo(1, 1),
             modifiers: Modifiers::default(),
+            context: None,
+            render_stats_overlay: false,
         }
     }
 
+    ///
+    /// Shows or hides an overlay in the top right corner with the [RenderStats] of the previous frame (see [Context::previous_render_stats]).
+    /// The overlay is only available if the GUI is created using [GUI::new].
+    ///
+    pub fn set_render_stats_overlay(&mut self, show: bool) {
+        self.render_stats_overlay = show;
+    }
+
     ///
     /// Initialises a new frame of the GUI and handles events.
     /// Construct the GUI (Add panels, widgets etc.) using the [egui::Context] in the callback function.
@@ -176,6 +190,11 @@ impl GUI {
 
         self.egui_context.begin_frame(egui_input);
         callback(&self.egui_context);
+        if self.render_stats_overlay {
+            if let Some(ref context) = self.context {
+                render_stats_overlay(&self.egui_context, context.previous_render_stats());
+            }
+        }
         *self.output.borrow_mut() = Some(self.egui_context.end_frame());
 
         for event in events.iter_mut() {
@@ -251,9 +270,28 @@ impl GUI {
             use glow::HasContext as _;
             self.painter.borrow().gl().disable(glow::FRAMEBUFFER_SRGB);
         }
+        if let Some(context) = &self.context {
+            context.invalidate_render_states();
+        }
     }
 }
 
+fn render_stats_overlay(egui_context: &egui::Context, stats: RenderStats) {
+    egui::Area::new("render_stats_overlay")
+        .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+        .interactable(false)
+        .show(egui_context, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.label(format!("Draw calls: {}", stats.draw_calls));
+                ui.label(format!("Triangles: {}", stats.triangles));
+                ui.label(format!("Instances: {}", stats.instances));
+                ui.label(format!("Program switches: {}", stats.program_switches));
+                ui.label(format!("Texture binds: {}", stats.texture_binds));
+                ui.label(format!("Programs compiled: {}", stats.programs_compiled));
+            });
+        });
+}
+
 impl From<&Key> for egui::Key {
     fn from(key: &Key) -> Self {
         use crate::control::Key::*;
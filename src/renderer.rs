@@ -29,11 +29,32 @@ pub enum RendererError {
     InvalidBufferLength(String, usize, usize),
     #[error("the material {0} is required by the geometry {1} but could not be found")]
     MissingMaterial(String, String),
+    #[error("failed compiling the shader program for material/effect {0} and geometry {1}: {2}")]
+    ShaderCompilation(u16, u16, #[source] CoreError),
+    #[error("the render graph pass {0} both reads and writes {1}")]
+    RenderGraphReadWrite(String, String),
+    #[error("the render graph pass {0} reads {1} which is not written by an earlier pass")]
+    RenderGraphMissingInput(String, String),
 }
 
 mod camera;
 pub use camera::*;
 
+mod render_graph;
+pub use render_graph::*;
+
+mod render_queue;
+pub use render_queue::*;
+
+mod bounding_volume_hierarchy;
+pub use bounding_volume_hierarchy::*;
+
+mod velocity_buffer;
+pub use velocity_buffer::*;
+
+mod material_pass;
+pub(crate) use material_pass::*;
+
 pub mod material;
 pub use material::*;
 
@@ -52,12 +73,15 @@ pub use object::*;
 pub mod control;
 pub use control::*;
 
+mod scene;
+pub use scene::*;
+
 macro_rules! impl_render_target_extensions_body {
     () => {
         ///
         /// Render the objects using the given camera and lights into this render target.
         /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
-        /// Also, objects outside the camera frustum are not rendered and the objects are rendered in the order given by [cmp_render_order].
+        /// Also, objects outside the camera frustum or not in the [Camera::cull_mask] are not rendered and the objects are rendered in the order given by [RenderQueue].
         ///
         pub fn render(
             &self,
@@ -71,7 +95,11 @@ macro_rules! impl_render_target_extensions_body {
         ///
         /// Render the objects using the given camera and lights into the part of this render target defined by the scissor box.
         /// Use an empty array for the `lights` argument, if the objects does not require lights to be rendered.
-        /// Also, objects outside the camera frustum are not rendered and the objects are rendered in the order given by [cmp_render_order].
+        /// Also, objects outside the camera frustum or not in the [Camera::cull_mask] are not rendered and the objects are rendered in the order given by [RenderQueue].
+        /// If order independent transparency is enabled on a [RenderTarget] (see [RenderTarget::set_order_independent_transparency]),
+        /// the objects with a transparent material are rendered using weighted blended order independent transparency after the other objects instead of being sorted.
+        /// If a depth pre-pass is enabled on a [RenderTarget] (see [RenderTarget::set_depth_pre_pass]), the depth of the opaque objects with a forward material is rendered before they are shaded.
+        /// If profiling is enabled (see [Context::set_profiling_enabled]), the geometry, lighting, depth pre-pass, forward and transparent passes are measured in the scopes `geometry pass`, `lighting pass`, `depth pre-pass`, `forward pass` and `transparent pass`.
         ///
         pub fn render_partially(
             &self,
@@ -79,10 +107,72 @@ macro_rules! impl_render_target_extensions_body {
             camera: &Camera,
             objects: impl IntoIterator<Item = impl Object>,
             lights: &[&dyn Light],
+        ) -> &Self {
+            self.render_visible_partially(
+                scissor_box,
+                camera,
+                objects.into_iter().filter(|o| is_visible(camera, o)),
+                lights,
+            )
+        }
+
+        ///
+        /// Render the objects which are inside the frustum of the camera using the given camera and lights into this render target.
+        /// The objects are found using the given [BoundingVolumeHierarchy] instead of testing every single object against the camera frustum,
+        /// which is faster when there is a large number of objects.
+        /// The objects must be the objects used to construct the bounding volume hierarchy and be in the same order.
+        ///
+        pub fn render_with_bounding_volume_hierarchy(
+            &self,
+            camera: &Camera,
+            objects: &[impl Object],
+            bounding_volume_hierarchy: &BoundingVolumeHierarchy,
+            lights: &[&dyn Light],
+        ) -> &Self {
+            self.render_partially_with_bounding_volume_hierarchy(
+                self.scissor_box(),
+                camera,
+                objects,
+                bounding_volume_hierarchy,
+                lights,
+            )
+        }
+
+        ///
+        /// Render the objects which are inside the frustum of the camera using the given camera and lights into the part of this render target defined by the scissor box.
+        /// The objects are found using the given [BoundingVolumeHierarchy] instead of testing every single object against the camera frustum,
+        /// which is faster when there is a large number of objects.
+        /// The objects must be the objects used to construct the bounding volume hierarchy and be in the same order.
+        ///
+        pub fn render_partially_with_bounding_volume_hierarchy(
+            &self,
+            scissor_box: ScissorBox,
+            camera: &Camera,
+            objects: &[impl Object],
+            bounding_volume_hierarchy: &BoundingVolumeHierarchy,
+            lights: &[&dyn Light],
+        ) -> &Self {
+            self.render_visible_partially(
+                scissor_box,
+                camera,
+                bounding_volume_hierarchy
+                    .frustum_cull(camera)
+                    .into_iter()
+                    .map(|i| &objects[i])
+                    .filter(|o| o.layers() & camera.cull_mask != 0),
+                lights,
+            )
+        }
+
+        fn render_visible_partially(
+            &self,
+            scissor_box: ScissorBox,
+            camera: &Camera,
+            objects: impl IntoIterator<Item = impl Object>,
+            lights: &[&dyn Light],
         ) -> &Self {
             let (mut deferred_objects, mut forward_objects): (Vec<_>, Vec<_>) = objects
                 .into_iter()
-                .filter(|o| camera.in_frustum(&o.aabb()))
                 .partition(|o| o.material_type() == MaterialType::Deferred);
 
             // Deferred
@@ -92,9 +182,9 @@ macro_rules! impl_render_target_extensions_body {
                 let viewport =
                     Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
                 geometry_pass_camera.set_viewport(viewport);
-                deferred_objects.sort_by(|a, b| cmp_render_order(&geometry_pass_camera, a, b));
-                let mut geometry_pass_texture = Texture2DArray::new_empty::<[u8; 4]>(
-                    &self.context,
+                deferred_objects =
+                    RenderQueue::new(&geometry_pass_camera, deferred_objects, lights).into_inner();
+                let mut geometry_pass_texture = self.context.transient_texture_2d_array::<[u8; 4]>(
                     viewport.width,
                     viewport.height,
                     3,
@@ -104,46 +194,132 @@ macro_rules! impl_render_target_extensions_body {
                     Wrapping::ClampToEdge,
                     Wrapping::ClampToEdge,
                 );
-                let mut geometry_pass_depth_texture = DepthTexture2D::new::<f32>(
-                    &self.context,
-                    viewport.width,
-                    viewport.height,
-                    Wrapping::ClampToEdge,
-                    Wrapping::ClampToEdge,
-                );
+                let mut geometry_pass_depth_texture =
+                    self.context.transient_depth_texture_2d::<f32>(
+                        viewport.width,
+                        viewport.height,
+                        Wrapping::ClampToEdge,
+                        Wrapping::ClampToEdge,
+                    );
                 let gbuffer_layers = [0, 1, 2];
-                RenderTarget::new(
-                    geometry_pass_texture.as_color_target(&gbuffer_layers, None),
-                    geometry_pass_depth_texture.as_depth_target(),
-                )
-                .clear(ClearState::default())
-                .write(|| {
-                    for object in deferred_objects {
-                        object.render(&geometry_pass_camera, lights);
-                    }
+                self.context.profile_scope("geometry pass", || {
+                    RenderTarget::new(
+                        geometry_pass_texture.as_color_target(&gbuffer_layers, None),
+                        geometry_pass_depth_texture.as_depth_target(),
+                    )
+                    .clear(ClearState::default())
+                    .write(|| {
+                        for object in deferred_objects.iter() {
+                            object.render(&geometry_pass_camera, lights);
+                        }
+                    });
                 });
 
                 // Lighting pass
-                self.apply_screen_effect_partially(
-                    scissor_box,
-                    &lighting_pass::LightingPassEffect {},
-                    camera,
-                    lights,
-                    Some(ColorTexture::Array {
-                        texture: &geometry_pass_texture,
-                        layers: &gbuffer_layers,
-                    }),
-                    Some(DepthTexture::Single(&geometry_pass_depth_texture)),
-                );
+                self.context.profile_scope("lighting pass", || {
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &lighting_pass::LightingPassEffect {},
+                        camera,
+                        lights,
+                        Some(ColorTexture::Array {
+                            texture: &geometry_pass_texture,
+                            layers: &gbuffer_layers,
+                        }),
+                        Some(DepthTexture::Single(&geometry_pass_depth_texture)),
+                    );
+                });
             }
 
             // Forward
-            forward_objects.sort_by(|a, b| cmp_render_order(camera, a, b));
-            self.write_partially(scissor_box, || {
-                for object in forward_objects {
-                    object.render(camera, lights);
-                }
+            let transparent_objects = if self.order_independent_transparency_enabled() {
+                let (transparent_objects, opaque_objects): (Vec<_>, Vec<_>) =
+                    forward_objects.into_iter().partition(|object| {
+                        let mut supported = false;
+                        object.use_material(&mut |material| {
+                            supported = material.material_type() == MaterialType::Transparent
+                                && OitAccumulationMaterial::is_supported(material.id())
+                        });
+                        supported
+                    });
+                forward_objects = opaque_objects;
+                transparent_objects
+            } else {
+                Vec::new()
+            };
+            forward_objects = RenderQueue::new(camera, forward_objects, lights).into_inner();
+            let mut depth_pre_passed = vec![false; forward_objects.len()];
+            if self.depth_pre_pass_enabled() {
+                self.context.profile_scope("depth pre-pass", || {
+                    self.write_partially(scissor_box, || {
+                        // The transparent objects are always last in the render queue
+                        for (object, pre_passed) in forward_objects
+                            .iter()
+                            .zip(depth_pre_passed.iter_mut())
+                            .take_while(|(o, _)| o.material_type() != MaterialType::Transparent)
+                        {
+                            object.use_material(&mut |material| {
+                                let render_states = material.render_states();
+                                if render_states.write_mask.depth {
+                                    let depth_material = DepthMaterial {
+                                        render_states: RenderStates {
+                                            write_mask: WriteMask::DEPTH,
+                                            cull: render_states.cull,
+                                            depth_test: render_states.depth_test,
+                                            ..Default::default()
+                                        },
+                                        ..Default::default()
+                                    };
+                                    object.render_with_material(&depth_material, camera, lights);
+                                    *pre_passed = true;
+                                }
+                            });
+                        }
+                    });
+                });
+            }
+            self.context.profile_scope("forward pass", || {
+                self.write_partially(scissor_box, || {
+                    for (object, pre_passed) in forward_objects.iter().zip(depth_pre_passed) {
+                        if pre_passed {
+                            object.use_material(&mut |material| {
+                                object.render_with_material(
+                                    &DepthPrePassShadingMaterial(material),
+                                    camera,
+                                    lights,
+                                )
+                            });
+                        } else {
+                            object.render(camera, lights);
+                        }
+                    }
+                });
             });
+
+            // Transparent
+            if !transparent_objects.is_empty() {
+                self.context.profile_scope("transparent pass", || {
+                    let texture = render_order_independent_transparency(
+                        &self.context,
+                        camera,
+                        self.target_depth_texture(),
+                        deferred_objects.iter().chain(forward_objects.iter()),
+                        &transparent_objects,
+                        lights,
+                    );
+                    self.apply_screen_effect_partially(
+                        scissor_box,
+                        &OrderIndependentTransparencyEffect {},
+                        camera,
+                        lights,
+                        Some(ColorTexture::Array {
+                            texture: &texture,
+                            layers: &[0, 1],
+                        }),
+                        None,
+                    );
+                });
+            }
             self
         }
 
@@ -179,15 +355,56 @@ macro_rules! impl_render_target_extensions_body {
             geometries: impl IntoIterator<Item = impl Geometry>,
             lights: &[&dyn Light],
         ) -> &Self {
+            self.try_render_partially_with_material(
+                scissor_box,
+                material,
+                camera,
+                geometries,
+                lights,
+            )
+            .expect("Failed compiling shader")
+        }
+
+        ///
+        /// Same as [Self::render_with_material], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_render_with_material(
+            &self,
+            material: &dyn Material,
+            camera: &Camera,
+            geometries: impl IntoIterator<Item = impl Geometry>,
+            lights: &[&dyn Light],
+        ) -> Result<&Self, RendererError> {
+            self.try_render_partially_with_material(
+                self.scissor_box(),
+                material,
+                camera,
+                geometries,
+                lights,
+            )
+        }
+
+        ///
+        /// Same as [Self::render_partially_with_material], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_render_partially_with_material(
+            &self,
+            scissor_box: ScissorBox,
+            material: &dyn Material,
+            camera: &Camera,
+            geometries: impl IntoIterator<Item = impl Geometry>,
+            lights: &[&dyn Light],
+        ) -> Result<&Self, RendererError> {
+            let mut result = Ok(());
             self.write_partially(scissor_box, || {
-                for geometry in geometries
+                result = geometries
                     .into_iter()
-                    .filter(|o| camera.in_frustum(&o.aabb()))
-                {
-                    render_with_material(&self.context, camera, geometry, material, lights);
-                }
+                    .filter(|o| is_visible(camera, o))
+                    .try_for_each(|geometry| {
+                        try_render_with_material(&self.context, camera, geometry, material, lights)
+                    });
             });
-            self
+            result.map(|_| self)
         }
 
         ///
@@ -228,23 +445,72 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            self.try_render_partially_with_effect(
+                scissor_box,
+                effect,
+                camera,
+                geometries,
+                lights,
+                color_texture,
+                depth_texture,
+            )
+            .expect("Failed compiling shader")
+        }
+
+        ///
+        /// Same as [Self::render_with_effect], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_render_with_effect(
+            &self,
+            effect: &dyn Effect,
+            camera: &Camera,
+            geometries: impl IntoIterator<Item = impl Geometry>,
+            lights: &[&dyn Light],
+            color_texture: Option<ColorTexture>,
+            depth_texture: Option<DepthTexture>,
+        ) -> Result<&Self, RendererError> {
+            self.try_render_partially_with_effect(
+                self.scissor_box(),
+                effect,
+                camera,
+                geometries,
+                lights,
+                color_texture,
+                depth_texture,
+            )
+        }
+
+        ///
+        /// Same as [Self::render_partially_with_effect], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_render_partially_with_effect(
+            &self,
+            scissor_box: ScissorBox,
+            effect: &dyn Effect,
+            camera: &Camera,
+            geometries: impl IntoIterator<Item = impl Geometry>,
+            lights: &[&dyn Light],
+            color_texture: Option<ColorTexture>,
+            depth_texture: Option<DepthTexture>,
+        ) -> Result<&Self, RendererError> {
+            let mut result = Ok(());
             self.write_partially(scissor_box, || {
-                for geometry in geometries
+                result = geometries
                     .into_iter()
-                    .filter(|o| camera.in_frustum(&o.aabb()))
-                {
-                    render_with_effect(
-                        &self.context,
-                        camera,
-                        geometry,
-                        effect,
-                        lights,
-                        color_texture,
-                        depth_texture,
-                    );
-                }
+                    .filter(|o| is_visible(camera, o))
+                    .try_for_each(|geometry| {
+                        try_render_with_effect(
+                            &self.context,
+                            camera,
+                            geometry,
+                            effect,
+                            lights,
+                            color_texture,
+                            depth_texture,
+                        )
+                    });
             });
-            self
+            result.map(|_| self)
         }
 
         ///
@@ -271,10 +537,37 @@ macro_rules! impl_render_target_extensions_body {
             camera: &Camera,
             lights: &[&dyn Light],
         ) -> &Self {
+            self.try_apply_screen_material_partially(scissor_box, material, camera, lights)
+                .expect("Failed compiling shader")
+        }
+
+        ///
+        /// Same as [Self::apply_screen_material], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_apply_screen_material(
+            &self,
+            material: &dyn Material,
+            camera: &Camera,
+            lights: &[&dyn Light],
+        ) -> Result<&Self, RendererError> {
+            self.try_apply_screen_material_partially(self.scissor_box(), material, camera, lights)
+        }
+
+        ///
+        /// Same as [Self::apply_screen_material_partially], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_apply_screen_material_partially(
+            &self,
+            scissor_box: ScissorBox,
+            material: &dyn Material,
+            camera: &Camera,
+            lights: &[&dyn Light],
+        ) -> Result<&Self, RendererError> {
+            let mut result = Ok(());
             self.write_partially(scissor_box, || {
-                apply_screen_material(&self.context, material, camera, lights)
+                result = try_apply_screen_material(&self.context, material, camera, lights)
             });
-            self
+            result.map(|_| self)
         }
 
         ///
@@ -312,8 +605,53 @@ macro_rules! impl_render_target_extensions_body {
             color_texture: Option<ColorTexture>,
             depth_texture: Option<DepthTexture>,
         ) -> &Self {
+            self.try_apply_screen_effect_partially(
+                scissor_box,
+                effect,
+                camera,
+                lights,
+                color_texture,
+                depth_texture,
+            )
+            .expect("Failed compiling shader")
+        }
+
+        ///
+        /// Same as [Self::apply_screen_effect], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_apply_screen_effect(
+            &self,
+            effect: &dyn Effect,
+            camera: &Camera,
+            lights: &[&dyn Light],
+            color_texture: Option<ColorTexture>,
+            depth_texture: Option<DepthTexture>,
+        ) -> Result<&Self, RendererError> {
+            self.try_apply_screen_effect_partially(
+                self.scissor_box(),
+                effect,
+                camera,
+                lights,
+                color_texture,
+                depth_texture,
+            )
+        }
+
+        ///
+        /// Same as [Self::apply_screen_effect_partially], except that an error is returned instead of a panic if the shader program could not be compiled.
+        ///
+        pub fn try_apply_screen_effect_partially(
+            &self,
+            scissor_box: ScissorBox,
+            effect: &dyn Effect,
+            camera: &Camera,
+            lights: &[&dyn Light],
+            color_texture: Option<ColorTexture>,
+            depth_texture: Option<DepthTexture>,
+        ) -> Result<&Self, RendererError> {
+            let mut result = Ok(());
             self.write_partially(scissor_box, || {
-                apply_screen_effect(
+                result = try_apply_screen_effect(
                     &self.context,
                     effect,
                     camera,
@@ -322,7 +660,7 @@ macro_rules! impl_render_target_extensions_body {
                     depth_texture,
                 )
             });
-            self
+            result.map(|_| self)
         }
     };
 }
@@ -354,6 +692,42 @@ macro_rules! impl_render_target_extensions {
     };
 }
 
+///
+/// Returns the settings used when rendering objects into a render target, which can only be changed for a [RenderTarget].
+///
+trait RenderSettings {
+    fn order_independent_transparency_enabled(&self) -> bool {
+        false
+    }
+
+    fn depth_pre_pass_enabled(&self) -> bool {
+        false
+    }
+
+    fn target_depth_texture(&self) -> Option<DepthTexture<'_>> {
+        None
+    }
+}
+
+impl RenderSettings for RenderTarget<'_> {
+    fn order_independent_transparency_enabled(&self) -> bool {
+        self.order_independent_transparency()
+    }
+
+    fn depth_pre_pass_enabled(&self) -> bool {
+        self.depth_pre_pass()
+    }
+
+    fn target_depth_texture(&self) -> Option<DepthTexture<'_>> {
+        self.depth_texture()
+    }
+}
+impl RenderSettings for ColorTarget<'_> {}
+impl RenderSettings for DepthTarget<'_> {}
+impl<C: TextureDataType, D: DepthTextureDataType> RenderSettings for RenderTargetMultisample<C, D> {}
+impl<C: TextureDataType> RenderSettings for ColorTargetMultisample<C> {}
+impl<D: DepthTextureDataType> RenderSettings for DepthTargetMultisample<D> {}
+
 impl_render_target_extensions!(RenderTarget<'a>);
 impl_render_target_extensions!(ColorTarget<'a>);
 impl_render_target_extensions!(DepthTarget<'a>);
@@ -368,6 +742,8 @@ impl_render_target_extensions!(DepthTargetMultisample<D: DepthTextureDataType>);
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
 ///
+/// **Note:** Panics if the shader program could not be compiled, use [try_render_with_material] to handle the error instead.
+///
 pub fn render_with_material(
     context: &Context,
     camera: &Camera,
@@ -375,20 +751,38 @@ pub fn render_with_material(
     material: impl Material,
     lights: &[&dyn Light],
 ) {
+    try_render_with_material(context, camera, geometry, material, lights)
+        .expect("Failed compiling shader")
+}
+
+///
+/// Render the given [Geometry] with the given [Material].
+/// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+/// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
+///
+/// Returns an error if the shader program could not be compiled, for example if the shader source of a custom material contains an error.
+///
+pub fn try_render_with_material(
+    context: &Context,
+    camera: &Camera,
+    geometry: impl Geometry,
+    material: impl Material,
+    lights: &[&dyn Light],
+) -> Result<(), RendererError> {
     let fragment_attributes = material.fragment_attributes();
-    let mut id = geometry.id(fragment_attributes).to_le_bytes().to_vec();
-    id.extend(material.id().to_le_bytes());
-    id.extend(lights.iter().map(|l| l.id()));
+    let geometry_id = geometry.id(fragment_attributes);
+    let material_id = material.id();
+    let id = program_key(geometry_id, material_id, lights);
 
     let mut programs = context.programs.write().unwrap();
-    let program = programs.entry(id).or_insert_with(|| {
+    let program = programs.get_or_try_insert_with(id, || {
         Program::from_source(
             context,
             &geometry.vertex_shader_source(fragment_attributes),
             &material.fragment_shader_source(lights),
         )
-        .expect("Failed compiling shader")
-    });
+        .map_err(|e| RendererError::ShaderCompilation(material_id, geometry_id, e))
+    })?;
     material.use_uniforms(program, camera, lights);
     geometry.draw(
         camera,
@@ -396,6 +790,7 @@ pub fn render_with_material(
         material.render_states(),
         fragment_attributes,
     );
+    Ok(())
 }
 
 ///
@@ -403,6 +798,8 @@ pub fn render_with_material(
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
 ///
+/// **Note:** Panics if the shader program could not be compiled, use [try_render_with_effect] to handle the error instead.
+///
 pub fn render_with_effect(
     context: &Context,
     camera: &Camera,
@@ -412,22 +809,51 @@ pub fn render_with_effect(
     color_texture: Option<ColorTexture>,
     depth_texture: Option<DepthTexture>,
 ) {
+    try_render_with_effect(
+        context,
+        camera,
+        geometry,
+        effect,
+        lights,
+        color_texture,
+        depth_texture,
+    )
+    .expect("Failed compiling shader")
+}
+
+///
+/// Render the given [Geometry] with the given [Effect].
+/// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+/// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+///
+/// Returns an error if the shader program could not be compiled, for example if the shader source of a custom effect contains an error.
+///
+pub fn try_render_with_effect(
+    context: &Context,
+    camera: &Camera,
+    geometry: impl Geometry,
+    effect: impl Effect,
+    lights: &[&dyn Light],
+    color_texture: Option<ColorTexture>,
+    depth_texture: Option<DepthTexture>,
+) -> Result<(), RendererError> {
     let fragment_attributes = effect.fragment_attributes();
-    let mut id = geometry.id(fragment_attributes).to_le_bytes().to_vec();
-    id.extend(effect.id(color_texture, depth_texture).to_le_bytes());
-    id.extend(lights.iter().map(|l| l.id()));
+    let geometry_id = geometry.id(fragment_attributes);
+    let effect_id = effect.id(color_texture, depth_texture);
+    let id = program_key(geometry_id, effect_id, lights);
 
     let mut programs = context.programs.write().unwrap();
-    let program = programs.entry(id).or_insert_with(|| {
+    let program = programs.get_or_try_insert_with(id, || {
         Program::from_source(
             context,
             &geometry.vertex_shader_source(fragment_attributes),
             &effect.fragment_shader_source(lights, color_texture, depth_texture),
         )
-        .expect("Failed compiling shader")
-    });
+        .map_err(|e| RendererError::ShaderCompilation(effect_id, geometry_id, e))
+    })?;
     effect.use_uniforms(program, camera, lights, color_texture, depth_texture);
     geometry.draw(camera, program, effect.render_states(), fragment_attributes);
+    Ok(())
 }
 
 ///
@@ -435,29 +861,46 @@ pub fn render_with_effect(
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
 ///
+/// **Note:** Panics if the shader program could not be compiled, use [try_apply_screen_material] to handle the error instead.
+///
 pub fn apply_screen_material(
     context: &Context,
     material: impl Material,
     camera: &Camera,
     lights: &[&dyn Light],
 ) {
+    try_apply_screen_material(context, material, camera, lights).expect("Failed compiling shader")
+}
+
+///
+/// Apply the given [Material] to the entire sceen.
+/// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+/// Use an empty array for the `lights` argument, if the material does not require lights to be rendered.
+///
+/// Returns an error if the shader program could not be compiled, for example if the shader source of a custom material contains an error.
+///
+pub fn try_apply_screen_material(
+    context: &Context,
+    material: impl Material,
+    camera: &Camera,
+    lights: &[&dyn Light],
+) -> Result<(), RendererError> {
     let fragment_attributes = material.fragment_attributes();
     if fragment_attributes.normal || fragment_attributes.position || fragment_attributes.tangents {
         panic!("Not possible to use the given material to render full screen, the full screen geometry only provides uv coordinates and color");
     }
-    let mut id = full_screen_id().to_le_bytes().to_vec();
-    id.extend(material.id().to_le_bytes());
-    id.extend(lights.iter().map(|l| l.id()));
+    let material_id = material.id();
+    let id = program_key(full_screen_id(), material_id, lights);
 
     let mut programs = context.programs.write().unwrap();
-    let program = programs.entry(id).or_insert_with(|| {
+    let program = programs.get_or_try_insert_with(id, || {
         Program::from_source(
             context,
             full_screen_vertex_shader_source(),
             &material.fragment_shader_source(lights),
         )
-        .expect("Failed compiling shader")
-    });
+        .map_err(|e| RendererError::ShaderCompilation(material_id, full_screen_id(), e))
+    })?;
     material.use_uniforms(program, camera, lights);
     full_screen_draw(
         context,
@@ -465,6 +908,7 @@ pub fn apply_screen_material(
         material.render_states(),
         camera.viewport(),
     );
+    Ok(())
 }
 
 ///
@@ -472,6 +916,8 @@ pub fn apply_screen_material(
 /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
 /// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
 ///
+/// **Note:** Panics if the shader program could not be compiled, use [try_apply_screen_effect] to handle the error instead.
+///
 pub fn apply_screen_effect(
     context: &Context,
     effect: impl Effect,
@@ -480,25 +926,93 @@ pub fn apply_screen_effect(
     color_texture: Option<ColorTexture>,
     depth_texture: Option<DepthTexture>,
 ) {
+    try_apply_screen_effect(
+        context,
+        effect,
+        camera,
+        lights,
+        color_texture,
+        depth_texture,
+    )
+    .expect("Failed compiling shader")
+}
+
+///
+/// Apply the given [Effect] to the entire sceen.
+/// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+/// Use an empty array for the `lights` argument, if the effect does not require lights to be rendered.
+///
+/// Returns an error if the shader program could not be compiled, for example if the shader source of a custom effect contains an error.
+///
+pub fn try_apply_screen_effect(
+    context: &Context,
+    effect: impl Effect,
+    camera: &Camera,
+    lights: &[&dyn Light],
+    color_texture: Option<ColorTexture>,
+    depth_texture: Option<DepthTexture>,
+) -> Result<(), RendererError> {
     let fragment_attributes = effect.fragment_attributes();
     if fragment_attributes.normal || fragment_attributes.position || fragment_attributes.tangents {
         panic!("Not possible to use the given effect to render full screen, the full screen geometry only provides uv coordinates and color");
     }
-    let mut id = full_screen_id().to_le_bytes().to_vec();
-    id.extend(effect.id(color_texture, depth_texture).to_le_bytes());
-    id.extend(lights.iter().map(|l| l.id()));
+    let effect_id = effect.id(color_texture, depth_texture);
+    let id = program_key(full_screen_id(), effect_id, lights);
 
     let mut programs = context.programs.write().unwrap();
-    let program = programs.entry(id).or_insert_with(|| {
+    let program = programs.get_or_try_insert_with(id, || {
         Program::from_source(
             context,
             full_screen_vertex_shader_source(),
             &effect.fragment_shader_source(lights, color_texture, depth_texture),
         )
-        .expect("Failed compiling shader")
-    });
+        .map_err(|e| RendererError::ShaderCompilation(effect_id, full_screen_id(), e))
+    })?;
     effect.use_uniforms(program, camera, lights, color_texture, depth_texture);
     full_screen_draw(context, program, effect.render_states(), camera.viewport());
+    Ok(())
+}
+
+///
+/// Compiles the [Program]s needed to render each of the given [Geometry] and [Material] combinations with the given lights
+/// and inserts them into the program cache of the context (see [Context::prewarm_program]), so that the programs are not compiled when rendering for the first time.
+/// Programs which are already in the cache are not compiled again and this does not affect the hit and miss counts of the cache.
+///
+pub fn prewarm_materials<'a>(
+    context: &Context,
+    combinations: impl IntoIterator<Item = (&'a dyn Geometry, &'a dyn Material, &'a [&'a dyn Light])>,
+) -> Result<(), RendererError> {
+    for (geometry, material, lights) in combinations {
+        let fragment_attributes = material.fragment_attributes();
+        let geometry_id = geometry.id(fragment_attributes);
+        let material_id = material.id();
+        let id = program_key(geometry_id, material_id, lights);
+
+        context.prewarm_program(id, || {
+            Program::from_source(
+                context,
+                &geometry.vertex_shader_source(fragment_attributes),
+                &material.fragment_shader_source(lights),
+            )
+            .map_err(|e| RendererError::ShaderCompilation(material_id, geometry_id, e))
+        })?;
+    }
+    Ok(())
+}
+
+///
+/// Returns whether or not the geometry is rendered using the camera, ie. whether it is in one of the layers in the cull mask of the camera and inside the camera frustum.
+///
+fn is_visible(camera: &Camera, geometry: impl Geometry) -> bool {
+    geometry.layers() & camera.cull_mask != 0 && camera.in_frustum(&geometry.aabb())
+}
+
+fn program_key(geometry_id: u16, material_id: u16, lights: &[&dyn Light]) -> Vec<u8> {
+    let mut id = geometry_id.to_le_bytes().to_vec();
+    id.extend(material_id.to_le_bytes());
+    id.extend((lights.len() as u32).to_le_bytes());
+    id.extend(lights.iter().map(|l| l.id()));
+    id
 }
 
 ///
@@ -548,6 +1062,7 @@ pub fn cmp_render_order(
 /// The pixel coordinate must be in physical pixels, where (viewport.x, viewport.y) indicate the bottom left corner of the viewport
 /// and (viewport.x + viewport.width, viewport.y + viewport.height) indicate the top right corner.
 /// Returns ```None``` if no geometry was hit between the near (`z_near`) and far (`z_far`) plane for this camera.
+/// Geometries which are not in one of the layers in the [Camera::cull_mask] are ignored.
 ///
 pub fn pick(
     context: &Context,
@@ -562,7 +1077,9 @@ pub fn pick(
         pos + dir * camera.z_near(),
         dir,
         camera.z_far() - camera.z_near(),
-        geometries,
+        geometries
+            .into_iter()
+            .filter(|g| g.layers() & camera.cull_mask != 0),
     )
 }
 
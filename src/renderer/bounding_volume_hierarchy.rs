@@ -0,0 +1,362 @@
+use crate::renderer::*;
+
+const MAX_LEAF_SIZE: usize = 4;
+
+#[derive(Clone, Debug)]
+struct BvhNode {
+    aabb: AxisAlignedBoundingBox,
+    parent: Option<usize>,
+    // The index of the first child node for a branch node, the children are always stored next to each other.
+    // The index of the first item in the item list for a leaf node.
+    first: usize,
+    // The number of items in a leaf node, zero for a branch node.
+    count: usize,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+///
+/// A bounding volume hierarchy, ie. a binary tree of axis aligned bounding boxes, over a list of axis aligned bounding boxes, typically the bounding boxes of a list of objects.
+/// It is used to speed up frustum culling (see [BoundingVolumeHierarchy::frustum_cull] and [RenderTarget::render_with_bounding_volume_hierarchy])
+/// and ray queries (see [BoundingVolumeHierarchy::ray_cast] and [BoundingVolumeHierarchy::ray_intersect]) when there is a large number of objects,
+/// since large parts of the list can be skipped by testing a single bounding box.
+///
+/// The items in the hierarchy are identified by their index in the list used to construct the hierarchy.
+/// When an item moves, use [BoundingVolumeHierarchy::set_aabb] to update the bounding box of the item and the bounding boxes containing it.
+/// When many items has moved, use [BoundingVolumeHierarchy::refit] to update all of the bounding boxes at once.
+/// Refitting keeps the structure of the tree, so if the items have moved a lot, the queries will be faster after calling [BoundingVolumeHierarchy::rebuild].
+///
+#[derive(Clone, Debug)]
+pub struct BoundingVolumeHierarchy {
+    nodes: Vec<BvhNode>,
+    items: Vec<usize>,
+    aabbs: Vec<AxisAlignedBoundingBox>,
+    leaves: Vec<usize>,
+}
+
+impl BoundingVolumeHierarchy {
+    ///
+    /// Constructs a new bounding volume hierarchy over the given bounding boxes.
+    ///
+    pub fn new(aabbs: impl IntoIterator<Item = AxisAlignedBoundingBox>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            items: Vec::new(),
+            aabbs: aabbs.into_iter().collect(),
+            leaves: Vec::new(),
+        };
+        bvh.rebuild();
+        bvh
+    }
+
+    ///
+    /// Constructs a new bounding volume hierarchy over the bounding boxes of the given geometries.
+    ///
+    pub fn from_geometries(geometries: impl IntoIterator<Item = impl Geometry>) -> Self {
+        Self::new(geometries.into_iter().map(|g| g.aabb()))
+    }
+
+    ///
+    /// Returns the number of items in the hierarchy.
+    ///
+    pub fn len(&self) -> usize {
+        self.aabbs.len()
+    }
+
+    ///
+    /// Returns whether or not the hierarchy contains any items.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.aabbs.is_empty()
+    }
+
+    ///
+    /// Returns the bounding box of the item with the given index.
+    ///
+    pub fn aabb(&self, index: usize) -> AxisAlignedBoundingBox {
+        self.aabbs[index]
+    }
+
+    ///
+    /// Sets the bounding box of the item with the given index and updates the bounding boxes containing the item.
+    ///
+    pub fn set_aabb(&mut self, index: usize, aabb: AxisAlignedBoundingBox) {
+        self.aabbs[index] = aabb;
+        let mut node = Some(self.leaves[index]);
+        while let Some(n) = node {
+            self.nodes[n].aabb = self.compute_node_aabb(n);
+            node = self.nodes[n].parent;
+        }
+    }
+
+    ///
+    /// Sets the bounding boxes of all the items to the bounding boxes of the given geometries and updates all of the bounding boxes in the hierarchy.
+    /// The given geometries must be the same number of geometries as the number of items in the hierarchy and be in the same order.
+    /// Use [BoundingVolumeHierarchy::rebuild] instead if the geometries have moved a lot since the hierarchy was built.
+    ///
+    pub fn refit(&mut self, geometries: impl IntoIterator<Item = impl Geometry>) {
+        self.aabbs
+            .iter_mut()
+            .zip(geometries)
+            .for_each(|(aabb, geometry)| *aabb = geometry.aabb());
+        // Children are always stored after their parent, so iterating backwards visits the children before their parent.
+        for n in (0..self.nodes.len()).rev() {
+            self.nodes[n].aabb = self.compute_node_aabb(n);
+        }
+    }
+
+    ///
+    /// Rebuilds the hierarchy from the current bounding boxes of the items.
+    ///
+    pub fn rebuild(&mut self) {
+        self.nodes.clear();
+        self.items = (0..self.aabbs.len()).collect();
+        self.leaves = vec![0; self.aabbs.len()];
+        if self.aabbs.is_empty() {
+            return;
+        }
+        let centers = self
+            .aabbs
+            .iter()
+            .map(|aabb| {
+                let center = 0.5 * (aabb.min() + aabb.max());
+                vec3(
+                    if center.x.is_finite() { center.x } else { 0.0 },
+                    if center.y.is_finite() { center.y } else { 0.0 },
+                    if center.z.is_finite() { center.z } else { 0.0 },
+                )
+            })
+            .collect::<Vec<_>>();
+        self.nodes.push(BvhNode {
+            aabb: AxisAlignedBoundingBox::EMPTY,
+            parent: None,
+            first: 0,
+            count: self.items.len(),
+        });
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let BvhNode { first, count, .. } = self.nodes[n];
+            if count <= MAX_LEAF_SIZE {
+                for i in first..first + count {
+                    self.leaves[self.items[i]] = n;
+                }
+                continue;
+            }
+
+            // Split at the median of the centers along the axis where the centers are most spread out.
+            let items = &mut self.items[first..first + count];
+            let mut min = vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+            let mut max = vec3(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY);
+            for i in items.iter() {
+                let c = centers[*i];
+                min = vec3(min.x.min(c.x), min.y.min(c.y), min.z.min(c.z));
+                max = vec3(max.x.max(c.x), max.y.max(c.y), max.z.max(c.z));
+            }
+            let extent = max - min;
+            let axis = if extent.x >= extent.y && extent.x >= extent.z {
+                0
+            } else if extent.y >= extent.z {
+                1
+            } else {
+                2
+            };
+            let half = count / 2;
+            items.select_nth_unstable_by(half, |a, b| {
+                centers[*a][axis].total_cmp(&centers[*b][axis])
+            });
+
+            let left = self.nodes.len();
+            self.nodes[n].first = left;
+            self.nodes[n].count = 0;
+            self.nodes.push(BvhNode {
+                aabb: AxisAlignedBoundingBox::EMPTY,
+                parent: Some(n),
+                first,
+                count: half,
+            });
+            self.nodes.push(BvhNode {
+                aabb: AxisAlignedBoundingBox::EMPTY,
+                parent: Some(n),
+                first: first + half,
+                count: count - half,
+            });
+            stack.push(left + 1);
+            stack.push(left);
+        }
+        // Children are always stored after their parent, so iterating backwards visits the children before their parent.
+        for n in (0..self.nodes.len()).rev() {
+            self.nodes[n].aabb = self.compute_node_aabb(n);
+        }
+    }
+
+    ///
+    /// Returns the indices of the items which are inside the frustum of the given camera, ie. the items that would not be culled by [Camera::in_frustum], in increasing order.
+    ///
+    pub fn frustum_cull(&self, camera: &Camera) -> Vec<usize> {
+        let mut result = Vec::new();
+        if self.is_empty() {
+            return result;
+        }
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !camera.in_frustum(&node.aabb) {
+                continue;
+            }
+            if node.is_leaf() {
+                result.extend(
+                    self.items[node.first..node.first + node.count]
+                        .iter()
+                        .filter(|i| camera.in_frustum(&self.aabbs[**i])),
+                );
+            } else {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            }
+        }
+        result.sort_unstable();
+        result
+    }
+
+    ///
+    /// Returns the indices of the items whose bounding box is hit by the ray starting at the given position in the given direction before the given maximum depth.
+    /// The indices are sorted by the distance from the ray origin to where the ray enters the bounding box of the item, closest first.
+    ///
+    pub fn ray_cast(&self, position: Vec3, direction: Vec3, max_depth: f32) -> Vec<usize> {
+        let mut result: Vec<(usize, f32)> = Vec::new();
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let inverse_direction = vec3(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut stack = vec![0];
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if ray_aabb_distance(&node.aabb, position, inverse_direction, max_depth).is_none() {
+                continue;
+            }
+            if node.is_leaf() {
+                result.extend(
+                    self.items[node.first..node.first + node.count]
+                        .iter()
+                        .filter_map(|i| {
+                            ray_aabb_distance(
+                                &self.aabbs[*i],
+                                position,
+                                inverse_direction,
+                                max_depth,
+                            )
+                            .map(|distance| (*i, distance))
+                        }),
+                );
+            } else {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+            }
+        }
+        result.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        result.into_iter().map(|(i, _)| i).collect()
+    }
+
+    ///
+    /// Same as [ray_intersect], except that only the geometries whose bounding box is hit by the ray are tested.
+    /// The given geometries must be the geometries used to construct the hierarchy and be in the same order.
+    ///
+    pub fn ray_intersect(
+        &self,
+        context: &Context,
+        position: Vec3,
+        direction: Vec3,
+        max_depth: f32,
+        geometries: &[impl Geometry],
+    ) -> Option<Vec3> {
+        let candidates = self.ray_cast(position, direction, max_depth);
+        if candidates.is_empty() {
+            return None;
+        }
+        ray_intersect(
+            context,
+            position,
+            direction,
+            max_depth,
+            candidates.into_iter().map(|i| &geometries[i]),
+        )
+    }
+
+    ///
+    /// Same as [pick], except that only the geometries whose bounding box is hit by the ray are tested.
+    /// The given geometries must be the geometries used to construct the hierarchy and be in the same order.
+    ///
+    pub fn pick(
+        &self,
+        context: &Context,
+        camera: &Camera,
+        pixel: impl Into<PhysicalPoint> + Copy,
+        geometries: &[impl Geometry],
+    ) -> Option<Vec3> {
+        let pos = camera.position_at_pixel(pixel);
+        let dir = camera.view_direction_at_pixel(pixel);
+        let position = pos + dir * camera.z_near();
+        let max_depth = camera.z_far() - camera.z_near();
+        let candidates = self
+            .ray_cast(position, dir, max_depth)
+            .into_iter()
+            .map(|i| &geometries[i])
+            .filter(|g| g.layers() & camera.cull_mask != 0)
+            .collect::<Vec<_>>();
+        if candidates.is_empty() {
+            return None;
+        }
+        ray_intersect(context, position, dir, max_depth, candidates)
+    }
+
+    fn compute_node_aabb(&self, n: usize) -> AxisAlignedBoundingBox {
+        let node = &self.nodes[n];
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        if node.is_leaf() {
+            for i in self.items[node.first..node.first + node.count].iter() {
+                aabb.expand_with_aabb(&self.aabbs[*i]);
+            }
+        } else {
+            aabb.expand_with_aabb(&self.nodes[node.first].aabb);
+            aabb.expand_with_aabb(&self.nodes[node.first + 1].aabb);
+        }
+        aabb
+    }
+}
+
+///
+/// Returns the distance along the ray to where it enters the bounding box or `None` if the ray does not hit the bounding box before the maximum depth.
+///
+fn ray_aabb_distance(
+    aabb: &AxisAlignedBoundingBox,
+    position: Vec3,
+    inverse_direction: Vec3,
+    max_depth: f32,
+) -> Option<f32> {
+    if aabb.is_empty() {
+        return None;
+    }
+    let t0 = (aabb.min() - position).mul_element_wise(inverse_direction);
+    let t1 = (aabb.max() - position).mul_element_wise(inverse_direction);
+    let mut t_min = 0.0f32;
+    let mut t_max = max_depth;
+    for axis in 0..3 {
+        let (near, far) = if t0[axis] <= t1[axis] {
+            (t0[axis], t1[axis])
+        } else {
+            (t1[axis], t0[axis])
+        };
+        // NaN happens when the ray is parallel to the slab and starts on its boundary, in which case the slab does not limit the ray.
+        if !near.is_nan() {
+            t_min = t_min.max(near);
+        }
+        if !far.is_nan() {
+            t_max = t_max.min(far);
+        }
+    }
+    (t_min <= t_max).then_some(t_min)
+}
@@ -16,6 +16,12 @@ pub struct Camera {
     pub tone_mapping: ToneMapping,
     /// The final color of renders using this camera is converted to this color space.
     pub target_color_space: ColorSpace,
+    /// A bitmask of the layers which are rendered using this camera. A geometry is only rendered if at least one of its layers (see [Geometry::layers]) is in this mask.
+    /// The default value contains all layers.
+    pub cull_mask: u32,
+    /// A sub-pixel offset in pixels which is applied to the [Camera::jittered_projection], for example to jitter the camera each frame when using [TemporalAntiAliasing].
+    /// The default value is no offset.
+    pub jitter: Vec2,
 }
 
 impl Camera {
@@ -37,6 +43,8 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             target_color_space: ColorSpace::default(),
+            cull_mask: u32::MAX,
+            jitter: vec2(0.0, 0.0),
         }
     }
 
@@ -64,6 +72,8 @@ impl Camera {
             ),
             tone_mapping: ToneMapping::default(),
             target_color_space: ColorSpace::default(),
+            cull_mask: u32::MAX,
+            jitter: vec2(0.0, 0.0),
         }
     }
 
@@ -92,6 +102,19 @@ impl Camera {
             10.0,
         )
     }
+
+    ///
+    /// Returns the projection matrix offset by the [Camera::jitter], which is used when rendering with this camera.
+    /// Use [three_d_asset::Camera::projection] to get the projection matrix without the jitter.
+    ///
+    pub fn jittered_projection(&self) -> Mat4 {
+        let viewport = self.camera.viewport();
+        Mat4::from_translation(vec3(
+            2.0 * self.jitter.x / viewport.width as f32,
+            2.0 * self.jitter.y / viewport.height as f32,
+            0.0,
+        )) * self.camera.projection()
+    }
 }
 
 use std::ops::Deref;
@@ -60,6 +60,26 @@ mod water;
 #[doc(inline)]
 pub use water::*;
 
+mod order_independent_transparency;
+#[doc(inline)]
+pub use order_independent_transparency::*;
+
+mod bloom;
+#[doc(inline)]
+pub use bloom::*;
+
+mod ambient_occlusion;
+#[doc(inline)]
+pub use ambient_occlusion::*;
+
+mod temporal_anti_aliasing;
+#[doc(inline)]
+pub use temporal_anti_aliasing::*;
+
+mod motion_blur;
+#[doc(inline)]
+pub use motion_blur::*;
+
 pub(crate) mod lighting_pass;
 
 use crate::renderer::*;
@@ -0,0 +1,269 @@
+use crate::renderer::*;
+
+///
+/// An effect that calculates screen space ambient occlusion, ie. how much each pixel is occluded by the nearby geometry, from a depth texture.
+/// The result is written as a gray scale color where white is no occlusion and black is full occlusion.
+///
+/// The normals are reconstructed from the depth texture, which results in flat shaded normals, unless a color texture containing the normals is also supplied.
+/// The normals can either be a [ColorTexture::Single] where the normals are encoded in the same way as the [NormalMaterial] does
+/// or a [ColorTexture::Array] where the second layer contains the normals encoded in the same way as in the geometry pass of [DeferredPhysicalMaterial].
+///
+/// The result is noisy, so use [ScreenSpaceAmbientOcclusion] to calculate a blurred ambient occlusion that can be applied to the ambient lighting.
+///
+#[derive(Clone, Debug)]
+pub struct AmbientOcclusionEffect {
+    /// The distance around a surface in which other geometry occludes it.
+    pub radius: f32,
+    /// The minimum depth difference before a surface is considered occluded, which avoids that a surface occludes itself.
+    pub bias: f32,
+    /// A value between 0.0 and 1.0 where 0.0 means no occlusion and 1.0 means full occlusion.
+    pub strength: f32,
+    /// The number of samples used for each pixel. More samples gives a more accurate result but is slower.
+    pub sample_count: u32,
+}
+
+impl Default for AmbientOcclusionEffect {
+    fn default() -> Self {
+        Self {
+            radius: 0.5,
+            bias: 0.025,
+            strength: 1.0,
+            sample_count: 16,
+        }
+    }
+}
+
+impl Effect for AmbientOcclusionEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        let normals = match color_texture {
+            Some(ColorTexture::Array { .. }) => "#define USE_GBUFFER_NORMALS\n",
+            Some(_) => "#define USE_NORMAL_TEXTURE\n",
+            None => "",
+        };
+        format!(
+            "{}{}{}\n{}\n{}",
+            "#include \"shared.frag\"\n",
+            normals,
+            color_texture
+                .map(|t| t.fragment_shader_source())
+                .unwrap_or_default(),
+            depth_texture
+                .expect("Must supply a depth texture to apply an ambient occlusion effect")
+                .fragment_shader_source(),
+            include_str!("shaders/ambient_occlusion_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 8
+            | color_texture.map(|t| t.id()).unwrap_or(0)
+            | depth_texture
+                .expect("Must supply a depth texture to apply an ambient occlusion effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        if let Some(color_texture) = color_texture {
+            color_texture.use_uniforms(program);
+        }
+        depth_texture
+            .expect("Must supply a depth texture to apply an ambient occlusion effect")
+            .use_uniforms(program);
+        let view_projection = camera.jittered_projection() * camera.view();
+        program.use_uniform("viewProjection", view_projection);
+        program.use_uniform("viewProjectionInverse", view_projection.invert().unwrap());
+        program.use_uniform("cameraPosition", camera.position());
+        program.use_uniform("radius", self.radius);
+        program.use_uniform("bias", self.bias);
+        program.use_uniform("strength", self.strength);
+        program.use_uniform("sampleCount", self.sample_count as i32);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Screen space ambient occlusion which darkens the ambient lighting in creases, holes and where objects are close to each other.
+/// This is especially useful for geometry without an occlusion texture, for example procedurally generated or instanced geometry.
+///
+/// Call [ScreenSpaceAmbientOcclusion::update] each frame with a depth texture rendered from the same camera as the final image, for example using a [DepthMaterial],
+/// and assign it to [AmbientLight::ambient_occlusion]. The ambient light then multiplies the ambient occlusion into the ambient lighting
+/// of all materials affected by lights, for example [PhysicalMaterial] and [DeferredPhysicalMaterial].
+///
+pub struct ScreenSpaceAmbientOcclusion {
+    context: Context,
+    texture: Option<TransientTexture<Texture2D>>,
+    viewport: Viewport,
+    /// The effect used to calculate the ambient occlusion before it is blurred.
+    pub effect: AmbientOcclusionEffect,
+}
+
+impl ScreenSpaceAmbientOcclusion {
+    ///
+    /// Creates a new screen space ambient occlusion with default settings.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            texture: None,
+            viewport: Viewport::new_at_origo(1, 1),
+            effect: AmbientOcclusionEffect::default(),
+        }
+    }
+
+    ///
+    /// Calculates the ambient occlusion from the given depth texture and optionally normals (see [AmbientOcclusionEffect]),
+    /// which must be rendered with the given camera and have the same size as the camera viewport.
+    ///
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        depth_texture: DepthTexture,
+        normal_texture: Option<ColorTexture>,
+    ) {
+        self.viewport = camera.viewport();
+        let viewport = Viewport::new_at_origo(self.viewport.width, self.viewport.height);
+        let mut occlusion_camera = camera.clone();
+        occlusion_camera.set_viewport(viewport);
+        let mut occlusion_texture = self.context.transient_texture_2d::<u8>(
+            viewport.width,
+            viewport.height,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        occlusion_texture.as_color_target(None).apply_screen_effect(
+            &self.effect,
+            &occlusion_camera,
+            &[],
+            normal_texture,
+            Some(depth_texture),
+        );
+
+        let mut texture = self.context.transient_texture_2d::<u8>(
+            viewport.width,
+            viewport.height,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        texture.as_color_target(None).apply_screen_effect(
+            &AmbientOcclusionBlurEffect {
+                texel_size: vec2(1.0 / viewport.width as f32, 1.0 / viewport.height as f32),
+            },
+            &occlusion_camera,
+            &[],
+            Some(ColorTexture::Single(&occlusion_texture)),
+            None,
+        );
+        self.texture = Some(texture);
+    }
+
+    ///
+    /// Returns the blurred ambient occlusion calculated in the last call to [ScreenSpaceAmbientOcclusion::update]
+    /// where the red channel contains the ambient occlusion.
+    ///
+    pub fn texture(&self) -> Option<&Texture2D> {
+        self.texture.as_deref()
+    }
+
+    pub(crate) fn use_uniforms(&self, program: &Program) {
+        program.use_texture(
+            "ambientOcclusionTexture",
+            self.texture
+                .as_ref()
+                .expect("Must call ScreenSpaceAmbientOcclusion::update before rendering"),
+        );
+        program.use_uniform(
+            "ambientOcclusionViewport",
+            vec4(
+                self.viewport.x as f32,
+                self.viewport.y as f32,
+                self.viewport.width as f32,
+                self.viewport.height as f32,
+            ),
+        );
+    }
+}
+
+struct AmbientOcclusionBlurEffect {
+    texel_size: Vec2,
+}
+
+impl Effect for AmbientOcclusionBlurEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/ambient_occlusion_blur.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 10 | 0b1u16 << 9 | color_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+        program.use_uniform("texelSize", self.texel_size);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::renderer::*;
+
+///
+/// An effect that makes bright areas glow by spreading their light to the surrounding area, for example light sources and emissive surfaces.
+/// Only the part of the color above the [BloomEffect::threshold] glows, so the colors should be rendered in high dynamic range,
+/// ie. into a floating point texture using a camera with [ToneMapping::None].
+///
+/// Call [BloomEffect::update] with the rendered color texture each frame to generate the bloom and then apply this effect with the same color texture,
+/// which adds the bloom to the color and applies the [ToneMapping] and target [ColorSpace] specified in the [Camera], similar to the [CopyEffect].
+/// The bloom is generated by downsampling the bright parts of the color texture into a chain of smaller and smaller textures and then blurring them back up,
+/// the textures are borrowed from the texture pool (see [Context::transient_texture_2d]).
+///
+pub struct BloomEffect {
+    context: Context,
+    mips: Vec<TransientTexture<Texture2D>>,
+    /// Only the part of a color where the brightest color channel is above this threshold contributes to the bloom.
+    pub threshold: f32,
+    /// A multiplier applied to the bloom before it is added to the color.
+    pub intensity: f32,
+    /// The radius of the blur applied when combining the downsampled textures, in texels of the downsampled textures.
+    /// Increasing it spreads the light further, but values much larger than 1 causes artifacts.
+    pub radius: f32,
+    /// The number of downsampled textures. More textures spreads the light further away from the bright areas and no bloom is added if this is zero.
+    pub mip_count: u32,
+}
+
+impl BloomEffect {
+    ///
+    /// Creates a new bloom effect with default settings.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            mips: Vec::new(),
+            threshold: 1.0,
+            intensity: 0.1,
+            radius: 1.0,
+            mip_count: 6,
+        }
+    }
+
+    ///
+    /// Generates the bloom from the given color texture, which must be done before applying this effect with the same color texture.
+    ///
+    pub fn update(&mut self, color_texture: ColorTexture) {
+        self.mips.clear();
+        let mut width = color_texture.width();
+        let mut height = color_texture.height();
+        for _ in 0..self.mip_count {
+            if width <= 1 && height <= 1 {
+                break;
+            }
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+            self.mips
+                .push(self.context.transient_texture_2d::<[f16; 4]>(
+                    width,
+                    height,
+                    Interpolation::Linear,
+                    Interpolation::Linear,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ));
+        }
+
+        // Downsample
+        for i in 0..self.mips.len() {
+            let (previous, rest) = self.mips.split_at_mut(i);
+            let source = previous.last().map(|t| ColorTexture::Single(t));
+            let source = source.unwrap_or(color_texture);
+            let effect = BloomDownsampleEffect {
+                texel_size: vec2(1.0 / source.width() as f32, 1.0 / source.height() as f32),
+                threshold: if i == 0 { self.threshold } else { 0.0 },
+            };
+            let target = &mut rest[0];
+            let camera = Camera::new_2d(Viewport::new_at_origo(target.width(), target.height()));
+            target.as_color_target(None).apply_screen_effect(
+                &effect,
+                &camera,
+                &[],
+                Some(source),
+                None,
+            );
+        }
+
+        // Upsample
+        for i in (1..self.mips.len()).rev() {
+            let (previous, rest) = self.mips.split_at_mut(i);
+            let source = &rest[0];
+            let effect = BloomUpsampleEffect {
+                texel_size: vec2(1.0 / source.width() as f32, 1.0 / source.height() as f32),
+                radius: self.radius,
+            };
+            let target = &mut previous[i - 1];
+            let camera = Camera::new_2d(Viewport::new_at_origo(target.width(), target.height()));
+            target.as_color_target(None).apply_screen_effect(
+                &effect,
+                &camera,
+                &[],
+                Some(ColorTexture::Single(source)),
+                None,
+            );
+        }
+
+        // Use a black texture when there are no downsampled textures, ie. no bloom
+        if self.mips.is_empty() {
+            let mut texture = self.context.transient_texture_2d::<[f16; 4]>(
+                1,
+                1,
+                Interpolation::Nearest,
+                Interpolation::Nearest,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            );
+            texture
+                .as_color_target(None)
+                .clear(ClearState::color(0.0, 0.0, 0.0, 0.0));
+            self.mips.push(texture);
+        }
+    }
+}
+
+impl Effect for BloomEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            color_texture
+                .expect("Must supply a color texture to apply a bloom effect")
+                .fragment_shader_source(),
+            ToneMapping::fragment_shader_source(),
+            ColorSpace::fragment_shader_source(),
+            include_str!("shaders/bloom_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 11
+            | color_texture
+                .expect("Must supply a color texture to apply a bloom effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.target_color_space.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a bloom effect")
+            .use_uniforms(program);
+        program.use_texture(
+            "bloomTexture",
+            self.mips
+                .first()
+                .expect("Must call BloomEffect::update before applying a bloom effect"),
+        );
+        program.use_uniform("bloomIntensity", self.intensity);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+struct BloomDownsampleEffect {
+    texel_size: Vec2,
+    threshold: f32,
+}
+
+impl Effect for BloomDownsampleEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/bloom_downsample.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 10 | color_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+        program.use_uniform("texelSize", self.texel_size);
+        program.use_uniform("threshold", self.threshold);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+struct BloomUpsampleEffect {
+    texel_size: Vec2,
+    radius: f32,
+}
+
+impl Effect for BloomUpsampleEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}\n{}",
+            color_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/bloom_upsample.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14 | 0b1u16 << 9 | color_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+        program.use_uniform("texelSize", self.texel_size);
+        program.use_uniform("radius", self.radius);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        // Adds the blurred smaller texture to the larger texture
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            blend: Blend::ADD,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
@@ -54,7 +54,7 @@ impl Effect for FogEffect {
     ) -> String {
         format!(
             "{}\n{}\n{}",
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             depth_texture
                 .expect("Must supply a depth texture to apply a fog effect")
                 .fragment_shader_source(),
@@ -91,7 +91,9 @@ impl Effect for FogEffect {
             .use_uniforms(program);
         program.use_uniform(
             "viewProjectionInverse",
-            (camera.projection() * camera.view()).invert().unwrap(),
+            (camera.jittered_projection() * camera.view())
+                .invert()
+                .unwrap(),
         );
         program.use_uniform("fogColor", self.color);
         program.use_uniform("fogDensity", self.density);
@@ -53,7 +53,9 @@ impl Effect for LightingPassEffect {
         }
         program.use_uniform_if_required(
             "viewProjectionInverse",
-            (camera.projection() * camera.view()).invert().unwrap(),
+            (camera.jittered_projection() * camera.view())
+                .invert()
+                .unwrap(),
         );
         program.use_uniform("debug_type", DebugType::None as i32);
     }
@@ -0,0 +1,129 @@
+use crate::renderer::*;
+
+///
+/// An effect that blurs each pixel along its movement on the screen during the frame, which makes movement look smoother, especially at low frame rates.
+/// The movement is given by a velocity texture, for example from a [VelocityBuffer], where the red and green channels contain the movement in UV coordinates since the previous frame.
+///
+/// Apply this effect with the rendered color texture, preferably in high dynamic range using a camera with [ToneMapping::None].
+/// Similar to the [CopyEffect], this effect applies the [ToneMapping] and target [ColorSpace] specified in the [Camera].
+///
+#[derive(Clone, Copy)]
+pub struct MotionBlurEffect<'a> {
+    /// The velocity of each pixel, which must be a [ColorTexture::Single] or a [ColorTexture::Array] where the first layer contains the velocity.
+    pub velocity_texture: ColorTexture<'a>,
+    /// A multiplier applied to the velocity, which corresponds to the fraction of the time between two frames that the shutter of a camera is open.
+    pub intensity: f32,
+    /// The number of samples along the movement of each pixel. More samples gives a smoother blur but is slower.
+    pub sample_count: u32,
+}
+
+impl<'a> MotionBlurEffect<'a> {
+    ///
+    /// Creates a new motion blur effect with the given velocity texture and default settings.
+    ///
+    pub fn new(velocity_texture: ColorTexture<'a>) -> Self {
+        Self {
+            velocity_texture,
+            intensity: 0.5,
+            sample_count: 8,
+        }
+    }
+}
+
+impl Effect for MotionBlurEffect<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        let velocity_source = match self.velocity_texture {
+            ColorTexture::Single(_) => {
+                "
+                uniform sampler2D velocityMap;
+                vec2 sample_velocity(vec2 uv)
+                {
+                    return texture(velocityMap, uv).xy;
+                }"
+            }
+            ColorTexture::Array { .. } => {
+                "
+                uniform sampler2DArray velocityMap;
+                uniform int velocityLayer;
+                vec2 sample_velocity(vec2 uv)
+                {
+                    return texture(velocityMap, vec3(uv, velocityLayer)).xy;
+                }"
+            }
+            ColorTexture::CubeMap { .. } => {
+                panic!("Cannot use a cube map as the velocity texture of a motion blur effect")
+            }
+        };
+        format!(
+            "{}\n{}\n{}\n{}\n{}",
+            color_texture
+                .expect("Must supply a color texture to apply a motion blur effect")
+                .fragment_shader_source(),
+            velocity_source,
+            ToneMapping::fragment_shader_source(),
+            ColorSpace::fragment_shader_source(),
+            include_str!("shaders/motion_blur_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        let velocity_id = match self.velocity_texture {
+            ColorTexture::Array { .. } => 0b1u16 << 9,
+            _ => 0,
+        };
+        0b1u16 << 14
+            | 0b1u16 << 13
+            | 0b1u16 << 10
+            | velocity_id
+            | color_texture
+                .expect("Must supply a color texture to apply a motion blur effect")
+                .id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        camera.tone_mapping.use_uniforms(program);
+        camera.target_color_space.use_uniforms(program);
+        color_texture
+            .expect("Must supply a color texture to apply a motion blur effect")
+            .use_uniforms(program);
+        match self.velocity_texture {
+            ColorTexture::Single(texture) => program.use_texture("velocityMap", texture),
+            ColorTexture::Array { texture, layers } => {
+                program.use_texture_array("velocityMap", texture);
+                program.use_uniform("velocityLayer", layers[0] as i32);
+            }
+            ColorTexture::CubeMap { .. } => {
+                panic!("Cannot use a cube map as the velocity texture of a motion blur effect")
+            }
+        }
+        program.use_uniform("intensity", self.intensity);
+        program.use_uniform("sampleCount", self.sample_count as i32);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
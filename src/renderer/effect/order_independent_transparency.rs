@@ -0,0 +1,167 @@
+use crate::renderer::*;
+
+///
+/// Resolves the result of weighted blended order independent transparency and blends it onto the render target.
+/// Weighted blended order independent transparency renders transparent objects correctly regardless of the order they are rendered in,
+/// which is not the case when sorting the objects, for example if the objects intersect or are nested inside each other.
+///
+/// This effect is applied by [RenderTarget::render] if order independent transparency is enabled on the render target (see [RenderTarget::set_order_independent_transparency]).
+/// It expects a [ColorTexture::Array] as input where the first layer contains the accumulated weighted color and the second layer contains the revealage.
+///
+#[derive(Clone, Default, Debug)]
+pub struct OrderIndependentTransparencyEffect {}
+
+impl Effect for OrderIndependentTransparencyEffect {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) -> String {
+        let color_texture = color_texture.expect(
+            "Must supply a color texture to apply an order independent transparency effect",
+        );
+        format!(
+            "{}\n{}",
+            color_texture.fragment_shader_source(),
+            include_str!("shaders/order_independent_transparency_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, _depth_texture: Option<DepthTexture>) -> u16 {
+        let color_texture = color_texture.expect(
+            "Must supply a color texture to apply an order independent transparency effect",
+        );
+        0b1u16 << 14 | 0b1u16 << 11 | color_texture.id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        _depth_texture: Option<DepthTexture>,
+    ) {
+        let color_texture = color_texture.expect(
+            "Must supply a color texture to apply an order independent transparency effect",
+        );
+        color_texture.use_uniforms(program);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            blend: Blend::TRANSPARENCY,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
+
+///
+/// Renders the transparent objects into an accumulation and a revealage layer of the returned texture,
+/// which can be resolved using the [OrderIndependentTransparencyEffect].
+/// The transparent objects are depth tested against the given depth texture of the render target, which the camera viewport is a part of,
+/// or if the render target does not have a depth texture, against the depth of the opaque objects which is then rendered again.
+///
+pub(crate) fn render_order_independent_transparency(
+    context: &Context,
+    camera: &Camera,
+    target_depth_texture: Option<DepthTexture>,
+    opaque_objects: impl IntoIterator<Item = impl Object>,
+    transparent_objects: &[impl Object],
+    lights: &[&dyn Light],
+) -> TransientTexture<Texture2DArray> {
+    let viewport = Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
+    let mut transparency_camera = camera.clone();
+    transparency_camera.set_viewport(viewport);
+    let mut texture = context.transient_texture_2d_array::<[f16; 4]>(
+        viewport.width,
+        viewport.height,
+        2,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    let mut depth_texture = context.transient_depth_texture_2d::<f32>(
+        viewport.width,
+        viewport.height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    if let Some(target_depth_texture) = target_depth_texture {
+        // Copy the part of the target depth covered by the camera viewport
+        let target_viewport = camera.viewport();
+        depth_texture.as_depth_target().copy_from(
+            target_depth_texture,
+            Viewport {
+                x: -target_viewport.x,
+                y: -target_viewport.y,
+                width: target_depth_texture.width(),
+                height: target_depth_texture.height(),
+            },
+        );
+    } else {
+        let depth_material = DepthMaterial {
+            render_states: RenderStates {
+                write_mask: WriteMask::DEPTH,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        depth_texture
+            .as_depth_target()
+            .clear(ClearState::default())
+            .write(|| {
+                for object in opaque_objects {
+                    object.render_with_material(&depth_material, &transparency_camera, &[]);
+                }
+            });
+    }
+
+    RenderTarget::new(
+        texture.as_color_target(&[0], None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(ClearState::color(0.0, 0.0, 0.0, 0.0))
+    .write(|| {
+        for object in transparent_objects {
+            object.use_material(&mut |material| {
+                object.render_with_material(
+                    &OitAccumulationMaterial(material),
+                    &transparency_camera,
+                    lights,
+                )
+            });
+        }
+    });
+
+    RenderTarget::new(
+        texture.as_color_target(&[1], None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(ClearState::color(1.0, 1.0, 1.0, 1.0))
+    .write(|| {
+        for object in transparent_objects {
+            object.use_material(&mut |material| {
+                object.render_with_material(
+                    &OitRevealageMaterial(material),
+                    &transparency_camera,
+                    lights,
+                )
+            });
+        }
+    });
+    texture
+}
@@ -0,0 +1,20 @@
+
+uniform vec2 texelSize;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    // 4x4 box filter which removes the noise from the rotation of the samples
+    float occlusion = 0.0;
+    for(int x = -2; x < 2; x++)
+    {
+        for(int y = -2; y < 2; y++)
+        {
+            occlusion += sample_color(uvs + (vec2(x, y) + 0.5) * texelSize).r;
+        }
+    }
+    outColor = vec4(vec3(occlusion / 16.0), 1.0);
+}
@@ -0,0 +1,77 @@
+
+uniform mat4 viewProjection;
+uniform mat4 viewProjectionInverse;
+uniform vec3 cameraPosition;
+uniform float radius;
+uniform float bias;
+uniform float strength;
+uniform int sampleCount;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 sample_normal(vec3 position)
+{
+#if defined(USE_GBUFFER_NORMALS)
+    // Same encoding as the geometry pass of the deferred physical material
+    vec4 n = sample_layer(uvs, 1);
+    vec2 n2 = n.xy*2.0 - 1.0;
+    float z = 1.0 - n2.x * n2.x - n2.y * n2.y;
+    if (z > 0.0001) {
+        z = sqrt(z);
+    }
+    return normalize(vec3(n2.x, n2.y, (int(floor(n.z * 255.0)) & 128) == 128 ? z: -z));
+#elif defined(USE_NORMAL_TEXTURE)
+    // Same encoding as the normal material
+    return normalize(sample_color(uvs).xyz * 2.0 - 1.0);
+#else
+    // Reconstructed from the depth, which results in flat shaded normals
+    vec3 normal = normalize(cross(dFdx(position), dFdy(position)));
+    return dot(normal, cameraPosition - position) < 0.0 ? -normal : normal;
+#endif
+}
+
+void main()
+{
+    float depth = sample_depth(uvs);
+    if(depth > 0.99999)
+    {
+        outColor = vec4(1.0);
+        return;
+    }
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+    vec3 normal = sample_normal(position);
+
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+
+    // Interleaved gradient noise used to rotate the samples, the resulting noise is removed by the blur
+    float noise = fract(52.9829189 * fract(dot(gl_FragCoord.xy, vec2(0.06711056, 0.00583715))));
+
+    float occlusion = 0.0;
+    for(int i = 0; i < sampleCount; i++)
+    {
+        // Cosine weighted direction in the hemisphere around the normal
+        vec2 xi = Hammersley(uint(i), uint(sampleCount));
+        float phi = 2.0 * PI * (xi.x + noise);
+        float sin_theta = sqrt(xi.y);
+        vec3 direction = vec3(cos(phi) * sin_theta, sin(phi) * sin_theta, sqrt(1.0 - xi.y));
+        direction = tangent * direction.x + bitangent * direction.y + normal * direction.z;
+
+        // More samples close to the position
+        float scale = float(i + 1) / float(sampleCount);
+        vec3 sample_position = position + direction * radius * mix(0.1, 1.0, scale * scale);
+
+        vec4 clip_position = viewProjection * vec4(sample_position, 1.0);
+        vec2 sample_uv = clip_position.xy / clip_position.w * 0.5 + 0.5;
+        vec3 occluder_position = world_pos_from_depth(viewProjectionInverse, sample_depth(sample_uv), sample_uv);
+
+        float range = smoothstep(0.0, 1.0, radius / max(distance(position, occluder_position), 0.0001));
+        float occluded = distance(cameraPosition, occluder_position) < distance(cameraPosition, sample_position) - bias ? 1.0 : 0.0;
+        occlusion += occluded * range;
+    }
+    float ambient_occlusion = 1.0 - strength * occlusion / float(max(sampleCount, 1));
+    outColor = vec4(vec3(ambient_occlusion), 1.0);
+}
@@ -0,0 +1,39 @@
+
+uniform vec2 texelSize;
+uniform float threshold;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 sample_offset(vec2 offset)
+{
+    return sample_color(uvs + offset * texelSize).rgb;
+}
+
+void main()
+{
+    // 13 tap downsampling filter which avoids the flickering of a box filter
+    vec3 a = sample_offset(vec2(-2.0, 2.0));
+    vec3 b = sample_offset(vec2(0.0, 2.0));
+    vec3 c = sample_offset(vec2(2.0, 2.0));
+    vec3 d = sample_offset(vec2(-2.0, 0.0));
+    vec3 e = sample_offset(vec2(0.0, 0.0));
+    vec3 f = sample_offset(vec2(2.0, 0.0));
+    vec3 g = sample_offset(vec2(-2.0, -2.0));
+    vec3 h = sample_offset(vec2(0.0, -2.0));
+    vec3 i = sample_offset(vec2(2.0, -2.0));
+    vec3 j = sample_offset(vec2(-1.0, 1.0));
+    vec3 k = sample_offset(vec2(1.0, 1.0));
+    vec3 l = sample_offset(vec2(-1.0, -1.0));
+    vec3 m = sample_offset(vec2(1.0, -1.0));
+    vec3 color = e * 0.125
+        + (a + c + g + i) * 0.03125
+        + (b + d + f + h) * 0.0625
+        + (j + k + l + m) * 0.125;
+
+    // Only keep the part of the color above the threshold
+    float brightness = max(color.r, max(color.g, color.b));
+    color *= max(brightness - threshold, 0.0) / max(brightness, 0.0001);
+    outColor = vec4(color, 1.0);
+}
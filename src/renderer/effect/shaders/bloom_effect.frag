@@ -0,0 +1,15 @@
+
+uniform sampler2D bloomTexture;
+uniform float bloomIntensity;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    outColor = sample_color(uvs);
+    outColor.rgb += bloomIntensity * texture(bloomTexture, uvs).rgb;
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -0,0 +1,21 @@
+
+uniform vec2 texelSize;
+uniform float radius;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 sample_offset(vec2 offset)
+{
+    return sample_color(uvs + offset * radius * texelSize).rgb;
+}
+
+void main()
+{
+    // 3x3 tent filter
+    vec3 color = sample_offset(vec2(0.0, 0.0)) * 4.0
+        + (sample_offset(vec2(0.0, 1.0)) + sample_offset(vec2(-1.0, 0.0)) + sample_offset(vec2(1.0, 0.0)) + sample_offset(vec2(0.0, -1.0))) * 2.0
+        + (sample_offset(vec2(-1.0, 1.0)) + sample_offset(vec2(1.0, 1.0)) + sample_offset(vec2(-1.0, -1.0)) + sample_offset(vec2(1.0, -1.0)));
+    outColor = vec4(color / 16.0, 1.0);
+}
@@ -0,0 +1,22 @@
+
+uniform float intensity;
+uniform int sampleCount;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    // Samples along the movement of the pixel during the frame, centered on the current position
+    vec2 velocity = intensity * sample_velocity(uvs);
+    outColor = vec4(0.0);
+    for(int i = 0; i < sampleCount; i++)
+    {
+        float t = sampleCount > 1 ? float(i) / float(sampleCount - 1) - 0.5 : 0.0;
+        outColor += sample_color(uvs - velocity * t);
+    }
+    outColor /= float(max(sampleCount, 1));
+    outColor.rgb = tone_mapping(outColor.rgb);
+    outColor.rgb = color_mapping(outColor.rgb);
+}
@@ -0,0 +1,14 @@
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 accumulation = sample_layer(uvs, 0);
+    float revealage = sample_layer(uvs, 1).r;
+    if (revealage >= 1.0) {
+        discard;
+    }
+    outColor = vec4(accumulation.rgb / clamp(accumulation.a, 1e-4, 5e4), 1.0 - revealage);
+}
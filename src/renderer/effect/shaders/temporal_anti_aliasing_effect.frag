@@ -0,0 +1,42 @@
+
+uniform sampler2D historyTexture;
+uniform mat4 viewProjectionInverse;
+uniform mat4 previousViewProjection;
+uniform float historyWeight;
+uniform vec2 texelSize;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 color = sample_color(uvs);
+
+    // The color range of the neighbourhood, used to reject history which is no longer valid
+    vec4 min_color = color;
+    vec4 max_color = color;
+    for(int x = -1; x <= 1; x++)
+    {
+        for(int y = -1; y <= 1; y++)
+        {
+            vec4 c = sample_color(uvs + vec2(x, y) * texelSize);
+            min_color = min(min_color, c);
+            max_color = max(max_color, c);
+        }
+    }
+
+    // Reprojection of the current position into the previous frame
+    float depth = sample_depth(uvs);
+    vec3 position = world_pos_from_depth(viewProjectionInverse, depth, uvs);
+    vec4 previous_position = previousViewProjection * vec4(position, 1.0);
+    vec2 history_uvs = previous_position.xy / previous_position.w * 0.5 + 0.5;
+    if(any(lessThan(history_uvs, vec2(0.0))) || any(greaterThan(history_uvs, vec2(1.0))))
+    {
+        outColor = color;
+        return;
+    }
+
+    vec4 history = clamp(texture(historyTexture, history_uvs), min_color, max_color);
+    outColor = mix(color, history, historyWeight);
+}
@@ -0,0 +1,202 @@
+use crate::renderer::*;
+
+const JITTER_SEQUENCE_LENGTH: u32 = 8;
+
+///
+/// Temporal anti-aliasing which removes jagged edges by accumulating the result of several frames,
+/// each rendered with the camera jittered by a different sub-pixel offset.
+/// Compared to the [FxaaEffect], it keeps thin geometry and text sharp but can result in ghosting when objects move.
+///
+/// Each frame, call [TemporalAntiAliasing::jitter] before rendering with the camera, then render the scene into a color and a depth texture,
+/// using either forward or deferred materials, and finally call [TemporalAntiAliasing::update] with the rendered textures.
+/// The anti-aliased result is available in [TemporalAntiAliasing::texture] and can for example be copied to the screen using the [CopyEffect].
+/// The textures are borrowed from the texture pool (see [Context::transient_texture_2d]).
+///
+/// The previous frame is found by reprojecting each pixel using the depth and the previous camera, so only the movement of the camera is taken into account.
+/// The history is clamped to the color range of the neighbouring pixels, which limits the ghosting from moving objects and pixels which were not previously visible.
+///
+pub struct TemporalAntiAliasing {
+    context: Context,
+    history: Option<TransientTexture<Texture2D>>,
+    previous_view_projection: Mat4,
+    frame: u32,
+    /// How much of the accumulated history is kept each frame, a value between 0.0 and 1.0.
+    /// A higher value gives a smoother result but more ghosting.
+    pub history_weight: f32,
+}
+
+impl TemporalAntiAliasing {
+    ///
+    /// Creates a new temporal anti-aliasing helper with default settings.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            history: None,
+            previous_view_projection: Mat4::identity(),
+            frame: 0,
+            history_weight: 0.9,
+        }
+    }
+
+    ///
+    /// Sets the [Camera::jitter] of the given camera to the sub-pixel offset for the next frame.
+    /// Must be called each frame before rendering with the camera.
+    ///
+    pub fn jitter(&mut self, camera: &mut Camera) {
+        self.frame = self.frame % JITTER_SEQUENCE_LENGTH + 1;
+        camera.jitter = vec2(halton(self.frame, 2) - 0.5, halton(self.frame, 3) - 0.5);
+    }
+
+    ///
+    /// Accumulates the given color texture, rendered with the given (jittered) camera, into the history.
+    /// The depth texture must be rendered with the same camera and is used to find the same position in the previous frames.
+    ///
+    pub fn update(
+        &mut self,
+        camera: &Camera,
+        color_texture: ColorTexture,
+        depth_texture: DepthTexture,
+    ) {
+        let viewport = Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
+        let mut resolve_camera = camera.clone();
+        resolve_camera.set_viewport(viewport);
+        let has_history = self
+            .history
+            .as_ref()
+            .map(|h| h.width() == viewport.width && h.height() == viewport.height)
+            .unwrap_or(false);
+        if !has_history {
+            let mut history = self.context.transient_texture_2d::<[f16; 4]>(
+                viewport.width,
+                viewport.height,
+                Interpolation::Linear,
+                Interpolation::Linear,
+                None,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            );
+            history
+                .as_color_target(None)
+                .clear(ClearState::color(0.0, 0.0, 0.0, 0.0));
+            self.history = Some(history);
+        }
+        let history = self.history.take().unwrap();
+
+        let mut texture = self.context.transient_texture_2d::<[f16; 4]>(
+            viewport.width,
+            viewport.height,
+            Interpolation::Linear,
+            Interpolation::Linear,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        texture.as_color_target(None).apply_screen_effect(
+            &TemporalAntiAliasingEffect {
+                history: &history,
+                view_projection_inverse: (camera.jittered_projection() * camera.view())
+                    .invert()
+                    .unwrap(),
+                previous_view_projection: self.previous_view_projection,
+                history_weight: if has_history {
+                    self.history_weight
+                } else {
+                    0.0
+                },
+                texel_size: vec2(1.0 / viewport.width as f32, 1.0 / viewport.height as f32),
+            },
+            &resolve_camera,
+            &[],
+            Some(color_texture),
+            Some(depth_texture),
+        );
+        self.history = Some(texture);
+        // The history converges towards the image without jitter
+        self.previous_view_projection = camera.projection() * camera.view();
+    }
+
+    ///
+    /// Returns the anti-aliased result of the last call to [TemporalAntiAliasing::update].
+    ///
+    pub fn texture(&self) -> Option<&Texture2D> {
+        self.history.as_deref()
+    }
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+struct TemporalAntiAliasingEffect<'a> {
+    history: &'a Texture2D,
+    view_projection_inverse: Mat4,
+    previous_view_projection: Mat4,
+    history_weight: f32,
+    texel_size: Vec2,
+}
+
+impl Effect for TemporalAntiAliasingEffect<'_> {
+    fn fragment_shader_source(
+        &self,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) -> String {
+        format!(
+            "{}{}\n{}\n{}",
+            "#include \"shared.frag\"\n",
+            color_texture.unwrap().fragment_shader_source(),
+            depth_texture.unwrap().fragment_shader_source(),
+            include_str!("shaders/temporal_anti_aliasing_effect.frag")
+        )
+    }
+
+    fn id(&self, color_texture: Option<ColorTexture>, depth_texture: Option<DepthTexture>) -> u16 {
+        0b1u16 << 14
+            | 0b1u16 << 10
+            | 0b1u16 << 8
+            | color_texture.unwrap().id()
+            | depth_texture.unwrap().id()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(
+        &self,
+        program: &Program,
+        _camera: &Camera,
+        _lights: &[&dyn Light],
+        color_texture: Option<ColorTexture>,
+        depth_texture: Option<DepthTexture>,
+    ) {
+        color_texture.unwrap().use_uniforms(program);
+        depth_texture.unwrap().use_uniforms(program);
+        program.use_texture("historyTexture", self.history);
+        program.use_uniform("viewProjectionInverse", self.view_projection_inverse);
+        program.use_uniform("previousViewProjection", self.previous_view_projection);
+        program.use_uniform("historyWeight", self.history_weight);
+        program.use_uniform("texelSize", self.texel_size);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            depth_test: DepthTest::Always,
+            cull: Cull::Back,
+            ..Default::default()
+        }
+    }
+}
@@ -113,10 +113,15 @@ impl Effect for WaterEffect {
         for (i, light) in lights.iter().enumerate() {
             light.use_uniforms(program, i as u32);
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform(
             "viewProjectionInverse",
-            (camera.projection() * camera.view()).invert().unwrap(),
+            (camera.jittered_projection() * camera.view())
+                .invert()
+                .unwrap(),
         );
         program.use_uniform("cameraPosition", camera.position());
         program.use_uniform(
@@ -7,6 +7,17 @@
 
 macro_rules! impl_geometry_body {
     ($inner:ident) => {
+        impl_geometry_body!($inner, without_layers);
+
+        fn layers(&self) -> u32 {
+            self.$inner().layers()
+        }
+
+        fn cast_shadows(&self) -> bool {
+            self.$inner().cast_shadows()
+        }
+    };
+    ($inner:ident, without_layers) => {
         fn draw(
             &self,
             camera: &Camera,
@@ -157,6 +168,23 @@ pub trait Geometry {
     ///
     fn aabb(&self) -> AxisAlignedBoundingBox;
 
+    ///
+    /// Returns a bitmask of the layers this geometry belongs to.
+    /// The geometry is only rendered using a camera if at least one of its layers is in the [Camera::cull_mask].
+    /// The default implementation returns `1`, ie. the geometry belongs to the first layer.
+    ///
+    fn layers(&self) -> u32 {
+        1
+    }
+
+    ///
+    /// Returns whether or not this geometry casts shadows, ie. whether it is rendered into shadow maps, for example when calling [DirectionalLight::generate_shadow_map].
+    /// The default implementation returns `true`.
+    ///
+    fn cast_shadows(&self) -> bool {
+        true
+    }
+
     ///
     /// For updating the animation of this geometry if it is animated, if not, this method does nothing.
     /// The time parameter should be some continious time, for example the time since start.
@@ -252,12 +280,22 @@ impl<T: Geometry> Geometry for std::sync::RwLock<T> {
         self.read().unwrap().aabb()
     }
 
+    fn layers(&self) -> u32 {
+        self.read().unwrap().layers()
+    }
+
+    fn cast_shadows(&self) -> bool {
+        self.read().unwrap().cast_shadows()
+    }
+
     fn animate(&mut self, time: f32) {
         self.write().unwrap().animate(time)
     }
 }
 
 struct BaseMesh {
+    primitive_type: PrimitiveType,
+    point_size: f32,
     indices: Option<ElementBuffer>,
     positions: VertexBuffer,
     normals: Option<VertexBuffer>,
@@ -267,11 +305,15 @@ struct BaseMesh {
 }
 
 impl BaseMesh {
-    pub fn new(context: &Context, cpu_mesh: &CpuMesh) -> Self {
+    pub fn new(context: &Context, cpu_mesh: &CpuMesh, primitive_type: PrimitiveType) -> Self {
         #[cfg(debug_assertions)]
-        cpu_mesh.validate().expect("invalid cpu mesh");
+        if primitive_type == PrimitiveType::Triangles {
+            cpu_mesh.validate().expect("invalid cpu mesh");
+        }
 
         Self {
+            primitive_type,
+            point_size: 1.0,
             indices: match &cpu_mesh.indices {
                 Indices::U8(ind) => Some(ElementBuffer::new_with_data(context, ind)),
                 Indices::U16(ind) => Some(ElementBuffer::new_with_data(context, ind)),
@@ -311,12 +353,19 @@ impl BaseMesh {
         attributes: FragmentAttributes,
     ) {
         self.use_attributes(program, attributes);
+        program.use_uniform_if_required("pointSize", self.point_size);
         if let Some(index_buffer) = &self.indices {
-            program.draw_elements(render_states, camera.viewport(), index_buffer)
+            program.draw_elements(
+                render_states,
+                camera.viewport(),
+                self.primitive_type,
+                index_buffer,
+            )
         } else {
             program.draw_arrays(
                 render_states,
                 camera.viewport(),
+                self.primitive_type,
                 self.positions.vertex_count(),
             )
         }
@@ -331,11 +380,13 @@ impl BaseMesh {
         instance_count: u32,
     ) {
         self.use_attributes(program, attributes);
+        program.use_uniform_if_required("pointSize", self.point_size);
 
         if let Some(index_buffer) = &self.indices {
             program.draw_elements_instanced(
                 render_states,
                 camera.viewport(),
+                self.primitive_type,
                 index_buffer,
                 instance_count,
             )
@@ -343,6 +394,7 @@ impl BaseMesh {
             program.draw_arrays_instanced(
                 render_states,
                 camera.viewport(),
+                self.primitive_type,
                 self.positions.vertex_count(),
                 instance_count,
             )
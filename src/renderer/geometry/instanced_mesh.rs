@@ -16,8 +16,10 @@ pub struct InstancedMesh {
     aabb_local: AxisAlignedBoundingBox,
     transformation: Mat4,
     current_transformation: Mat4,
+    previous_transformation: Mat4,
     animation: Option<Box<dyn Fn(f32) -> Mat4 + Send + Sync>>,
     instances: Instances,
+    previous_instance_transformations: Vec<Mat4>,
 }
 
 impl InstancedMesh {
@@ -27,22 +29,58 @@ impl InstancedMesh {
     /// The model is rendered in as many instances as there are attributes in [Instances] given as input.
     ///
     pub fn new(context: &Context, instances: &Instances, cpu_mesh: &CpuMesh) -> Self {
+        Self::new_with_primitive_type(context, instances, cpu_mesh, PrimitiveType::Triangles)
+    }
+
+    ///
+    /// Same as [InstancedMesh::new] except that the vertices are assembled into primitives of the given [PrimitiveType],
+    /// see [Mesh::new_with_primitive_type].
+    ///
+    pub fn new_with_primitive_type(
+        context: &Context,
+        instances: &Instances,
+        cpu_mesh: &CpuMesh,
+        primitive_type: PrimitiveType,
+    ) -> Self {
         let aabb = cpu_mesh.compute_aabb();
         let mut instanced_mesh = Self {
             context: context.clone(),
-            base_mesh: BaseMesh::new(context, cpu_mesh),
+            base_mesh: BaseMesh::new(context, cpu_mesh, primitive_type),
             instance_buffers: RwLock::new((Default::default(), vec3(0.0, 0.0, 0.0))),
             aabb,
             aabb_local: aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
+            previous_transformation: Mat4::identity(),
             animation: None,
             instances: instances.clone(),
+            previous_instance_transformations: Vec::new(),
         };
         instanced_mesh.set_instances(instances);
         instanced_mesh
     }
 
+    ///
+    /// Returns the [PrimitiveType] which defines how the vertices of each instance are assembled into primitives.
+    ///
+    pub fn primitive_type(&self) -> PrimitiveType {
+        self.base_mesh.primitive_type
+    }
+
+    ///
+    /// Returns the size in pixels of the points when the primitive type is [PrimitiveType::Points].
+    ///
+    pub fn point_size(&self) -> f32 {
+        self.base_mesh.point_size
+    }
+
+    ///
+    /// Sets the size in pixels of the points when the primitive type is [PrimitiveType::Points], see [Mesh::set_point_size].
+    ///
+    pub fn set_point_size(&mut self, point_size: f32) {
+        self.base_mesh.point_size = point_size;
+    }
+
     ///
     /// Returns the local to world transformation applied to all instances.
     ///
@@ -53,12 +91,23 @@ impl InstancedMesh {
     ///
     /// Set the local to world transformation applied to all instances.
     /// This is applied before the transform for each instance.
+    /// The transformation before this call is stored as the previous transformation, see [Self::set_previous_transformation].
     ///
     pub fn set_transformation(&mut self, transformation: Mat4) {
         self.transformation = transformation;
+        self.previous_transformation = self.current_transformation;
         self.current_transformation = transformation;
     }
 
+    ///
+    /// Set the local to world transformation applied to all instances in the previous frame, which is used to find the velocity of the instances (see [VelocityMaterial]).
+    /// The previous transformation is automatically set to the transformation before the last call to [Self::set_transformation] or [Geometry::animate],
+    /// so use this method if the mesh is no longer moving or if it is moved more than once each frame.
+    ///
+    pub fn set_previous_transformation(&mut self, transformation: Mat4) {
+        self.previous_transformation = transformation;
+    }
+
     ///
     /// Specifies a function which takes a time parameter as input and returns a transformation that should be applied to this mesh at the given time.
     /// To actually animate this instanced mesh, call [Geometry::animate] at each frame which in turn evaluates the animation function defined by this method.
@@ -75,10 +124,14 @@ impl InstancedMesh {
 
     ///
     /// Update the instances.
+    /// The transformations of the instances before this call are used to find the velocity of each instance (see [VelocityMaterial]),
+    /// so call this method again with the same instances if the instances are no longer moving.
     ///
     pub fn set_instances(&mut self, instances: &Instances) {
         #[cfg(debug_assertions)]
         instances.validate().expect("invalid instances");
+        self.previous_instance_transformations =
+            std::mem::take(&mut self.instances.transformations);
         self.instances = instances.clone();
         self.update_aabb();
 
@@ -123,6 +176,16 @@ impl InstancedMesh {
 
         // Next, we can compute the instance buffers with that ordering.
         let instance_buffers = &mut s.0;
+        // The previous transformations of the instances, which are used to find the velocity of each instance
+        let previous_transformations = indices
+            .iter()
+            .map(|i| {
+                self.previous_instance_transformations
+                    .get(*i)
+                    .copied()
+                    .unwrap_or(self.instances.transformations[*i])
+            })
+            .collect::<Vec<_>>();
 
         if indices
             .iter()
@@ -140,6 +203,16 @@ impl InstancedMesh {
                         .collect::<Vec<_>>(),
                 ),
             );
+            instance_buffers.insert(
+                "previous_instance_translation".to_string(),
+                InstanceBuffer::new_with_data(
+                    &self.context,
+                    &previous_transformations
+                        .iter()
+                        .map(|t| t.w.truncate())
+                        .collect::<Vec<_>>(),
+                ),
+            );
         } else {
             let mut row1 = Vec::new();
             let mut row2 = Vec::new();
@@ -162,6 +235,28 @@ impl InstancedMesh {
                 "row3".to_string(),
                 InstanceBuffer::new_with_data(&self.context, &row3),
             );
+
+            let mut previous_row1 = Vec::new();
+            let mut previous_row2 = Vec::new();
+            let mut previous_row3 = Vec::new();
+            for transformation in previous_transformations.iter() {
+                previous_row1.push(transformation.row(0));
+                previous_row2.push(transformation.row(1));
+                previous_row3.push(transformation.row(2));
+            }
+
+            instance_buffers.insert(
+                "previous_row1".to_string(),
+                InstanceBuffer::new_with_data(&self.context, &previous_row1),
+            );
+            instance_buffers.insert(
+                "previous_row2".to_string(),
+                InstanceBuffer::new_with_data(&self.context, &previous_row2),
+            );
+            instance_buffers.insert(
+                "previous_row3".to_string(),
+                InstanceBuffer::new_with_data(&self.context, &previous_row3),
+            );
         }
 
         if let Some(texture_transforms) = &self.instances.texture_transformations {
@@ -235,14 +330,22 @@ impl Geometry for InstancedMesh {
                 return;
             }
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform("modelMatrix", self.current_transformation);
+        program.use_uniform_if_required("previousModelMatrix", self.previous_transformation);
 
         for attribute_name in [
             "instance_translation",
             "row1",
             "row2",
             "row3",
+            "previous_instance_translation",
+            "previous_row1",
+            "previous_row2",
+            "previous_row3",
             "tex_transform_row1",
             "tex_transform_row2",
             "instance_color",
@@ -303,7 +406,7 @@ impl Geometry for InstancedMesh {
             } else {
                 ""
             },
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             include_str!("shaders/mesh.vert"),
         )
     }
@@ -347,6 +450,7 @@ impl Geometry for InstancedMesh {
 
     fn animate(&mut self, time: f32) {
         if let Some(animation) = &self.animation {
+            self.previous_transformation = self.current_transformation;
             self.current_transformation = self.transformation * animation(time);
         }
     }
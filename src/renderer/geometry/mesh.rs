@@ -5,6 +5,7 @@ use super::BaseMesh;
 
 ///
 /// A triangle mesh [Geometry].
+/// Use [Mesh::new_with_primitive_type] to render the vertices as lines or points instead.
 ///
 pub struct Mesh {
     base_mesh: BaseMesh,
@@ -12,6 +13,7 @@ pub struct Mesh {
     aabb: AxisAlignedBoundingBox,
     transformation: Mat4,
     current_transformation: Mat4,
+    previous_transformation: Mat4,
     animation: Option<Box<dyn Fn(f32) -> Mat4 + Send + Sync>>,
 }
 
@@ -21,17 +23,50 @@ impl Mesh {
     /// All data in the [CpuMesh] is transfered to the GPU, so make sure to remove all unnecessary data from the [CpuMesh] before calling this method.
     ///
     pub fn new(context: &Context, cpu_mesh: &CpuMesh) -> Self {
+        Self::new_with_primitive_type(context, cpu_mesh, PrimitiveType::Triangles)
+    }
+
+    ///
+    /// Creates a new mesh from the given [CpuMesh] where the vertices are assembled into primitives of the given [PrimitiveType].
+    /// For example, use [PrimitiveType::Lines] to render each pair of indices (or positions if the mesh has no indices) as a line segment
+    /// or [PrimitiveType::Points] to render each vertex as a point.
+    /// All data in the [CpuMesh] is transfered to the GPU, so make sure to remove all unnecessary data from the [CpuMesh] before calling this method.
+    ///
+    /// **Note:** Lines are always one pixel wide and the size of points is set using [Mesh::set_point_size]. Lines and points are not affected by the normals of the mesh.
+    ///
+    pub fn new_with_primitive_type(
+        context: &Context,
+        cpu_mesh: &CpuMesh,
+        primitive_type: PrimitiveType,
+    ) -> Self {
         let aabb = cpu_mesh.compute_aabb();
         Self {
             context: context.clone(),
-            base_mesh: BaseMesh::new(context, cpu_mesh),
+            base_mesh: BaseMesh::new(context, cpu_mesh, primitive_type),
             aabb,
             transformation: Mat4::identity(),
             current_transformation: Mat4::identity(),
+            previous_transformation: Mat4::identity(),
             animation: None,
         }
     }
 
+    ///
+    /// Creates a new mesh from the given [PointCloud] where each point is rendered as a point primitive, see [PrimitiveType::Points].
+    /// The colors of the points, if any, are used as vertex colors.
+    ///
+    pub fn from_point_cloud(context: &Context, point_cloud: &PointCloud) -> Self {
+        Self::new_with_primitive_type(
+            context,
+            &CpuMesh {
+                positions: point_cloud.positions.clone(),
+                colors: point_cloud.colors.clone(),
+                ..Default::default()
+            },
+            PrimitiveType::Points,
+        )
+    }
+
     pub(in crate::renderer) fn set_transformation_2d(&mut self, transformation: Mat3) {
         self.set_transformation(Mat4::new(
             transformation.x.x,
@@ -53,6 +88,28 @@ impl Mesh {
         ));
     }
 
+    ///
+    /// Returns the [PrimitiveType] which defines how the vertices of this mesh are assembled into primitives.
+    ///
+    pub fn primitive_type(&self) -> PrimitiveType {
+        self.base_mesh.primitive_type
+    }
+
+    ///
+    /// Returns the size in pixels of the points when the primitive type is [PrimitiveType::Points].
+    ///
+    pub fn point_size(&self) -> f32 {
+        self.base_mesh.point_size
+    }
+
+    ///
+    /// Sets the size in pixels of the points when the primitive type is [PrimitiveType::Points]. The default value is `1.0`.
+    /// The maximum size depends on the graphics driver.
+    ///
+    pub fn set_point_size(&mut self, point_size: f32) {
+        self.base_mesh.point_size = point_size;
+    }
+
     ///
     /// Returns the local to world transformation applied to this mesh.
     ///
@@ -63,12 +120,23 @@ impl Mesh {
     ///
     /// Set the local to world transformation applied to this mesh.
     /// If any animation method is set using [Self::set_animation], the transformation from that method is applied before this transformation.
+    /// The transformation before this call is stored as the previous transformation, see [Self::set_previous_transformation].
     ///
     pub fn set_transformation(&mut self, transformation: Mat4) {
         self.transformation = transformation;
+        self.previous_transformation = self.current_transformation;
         self.current_transformation = transformation;
     }
 
+    ///
+    /// Set the local to world transformation applied to this mesh in the previous frame, which is used to find the velocity of the mesh (see [VelocityMaterial]).
+    /// The previous transformation is automatically set to the transformation before the last call to [Self::set_transformation] or [Geometry::animate],
+    /// so use this method if the mesh is no longer moving or if it is moved more than once each frame.
+    ///
+    pub fn set_previous_transformation(&mut self, transformation: Mat4) {
+        self.previous_transformation = transformation;
+    }
+
     ///
     /// Specifies a function which takes a time parameter as input and returns a transformation that should be applied to this mesh at the given time.
     /// To actually animate this mesh, call [Geometry::animate] at each frame which in turn evaluates the animation function defined by this method.
@@ -97,6 +165,7 @@ impl Geometry for Mesh {
 
     fn animate(&mut self, time: f32) {
         if let Some(animation) = &self.animation {
+            self.previous_transformation = self.current_transformation;
             self.current_transformation = self.transformation * animation(time);
         }
     }
@@ -117,8 +186,12 @@ impl Geometry for Mesh {
             }
         }
 
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform("modelMatrix", self.current_transformation);
+        program.use_uniform_if_required("previousModelMatrix", self.previous_transformation);
 
         self.base_mesh
             .draw(program, render_states, camera, attributes);
@@ -147,7 +220,7 @@ impl Geometry for Mesh {
             } else {
                 ""
             },
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             include_str!("shaders/mesh.vert"),
         )
     }
@@ -96,7 +96,7 @@ impl ParticleSystem {
 
         let mut particles_system = Self {
             context: context.clone(),
-            base_mesh: BaseMesh::new(context, cpu_mesh),
+            base_mesh: BaseMesh::new(context, cpu_mesh, PrimitiveType::Triangles),
             instance_buffers: HashMap::new(),
             acceleration,
             instance_count: 0,
@@ -238,7 +238,7 @@ impl Geometry for ParticleSystem {
             } else {
                 ""
             },
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             include_str!("shaders/mesh.vert"),
         )
     }
@@ -258,8 +258,12 @@ impl Geometry for ParticleSystem {
                 return;
             }
         }
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform("modelMatrix", self.transformation);
+        program.use_uniform_if_required("previousModelMatrix", self.transformation);
         program.use_uniform("acceleration", self.acceleration);
         program.use_uniform("time", self.time);
 
@@ -1,6 +1,8 @@
 
 uniform mat4 viewProjection;
 uniform mat4 modelMatrix;
+uniform mat4 previousModelMatrix;
+uniform float pointSize;
 in vec3 position;
 
 #ifdef PARTICLES
@@ -12,15 +14,20 @@ uniform float time;
 
 #ifdef USE_INSTANCE_TRANSLATIONS
 in vec3 instance_translation;
+in vec3 previous_instance_translation;
 #endif
 
 #ifdef USE_INSTANCE_TRANSFORMS
 in vec4 row1;
 in vec4 row2;
 in vec4 row3;
+in vec4 previous_row1;
+in vec4 previous_row2;
+in vec4 previous_row3;
 #endif
 
 out vec3 pos;
+out vec3 previous_pos;
 
 #ifdef USE_NORMALS 
 uniform mat4 normalMatrix;
@@ -77,9 +84,30 @@ void main()
     worldPosition.xyz += instance_translation;
 #endif
     gl_Position = viewProjection * worldPosition;
+    gl_PointSize = pointSize;
 
     pos = worldPosition.xyz;
 
+    // *** PREVIOUS POSITION ***
+    mat4 previousLocal2World = previousModelMatrix;
+#ifdef USE_INSTANCE_TRANSFORMS
+    mat4 previousTransform;
+    previousTransform[0] = vec4(previous_row1.x, previous_row2.x, previous_row3.x, 0.0);
+    previousTransform[1] = vec4(previous_row1.y, previous_row2.y, previous_row3.y, 0.0);
+    previousTransform[2] = vec4(previous_row1.z, previous_row2.z, previous_row3.z, 0.0);
+    previousTransform[3] = vec4(previous_row1.w, previous_row2.w, previous_row3.w, 1.0);
+    previousLocal2World *= previousTransform;
+#endif
+    vec4 previousWorldPosition = previousLocal2World * vec4(position, 1.);
+    previousWorldPosition /= previousWorldPosition.w;
+#ifdef PARTICLES
+    previousWorldPosition.xyz += start_position + start_velocity * time + 0.5 * acceleration * time * time;
+#endif
+#ifdef USE_INSTANCE_TRANSLATIONS 
+    previousWorldPosition.xyz += previous_instance_translation;
+#endif
+    previous_pos = previousWorldPosition.xyz;
+
     // *** NORMAL ***
 #ifdef USE_NORMALS 
 #ifdef USE_INSTANCE_TRANSFORMS
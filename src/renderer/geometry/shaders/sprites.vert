@@ -11,6 +11,7 @@ in vec2 uv_coordinate;
 out vec2 uvs;
 out vec4 col;
 out vec3 pos;
+out vec3 previous_pos;
 
 void main()
 {
@@ -37,5 +38,6 @@ void main()
                 center.x, center.y, center.z, 1.0);
     vec4 world_pos = instanced_transform * transformation * vec4(position, 1.);
     pos = world_pos.xyz / world_pos.w;
+    previous_pos = pos;
     gl_Position = viewProjection * world_pos;
 }
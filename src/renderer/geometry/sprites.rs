@@ -85,7 +85,10 @@ impl Sprites {
 
     fn draw(&self, program: &Program, render_states: RenderStates, camera: &Camera) {
         program.use_uniform("eye", camera.position());
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform("transformation", self.transformation);
         program.use_vertex_attribute("position", &self.position_buffer);
         program.use_vertex_attribute("uv_coordinate", &self.uv_buffer);
@@ -94,6 +97,7 @@ impl Sprites {
         program.draw_arrays_instanced(
             render_states,
             camera.viewport(),
+            PrimitiveType::Triangles,
             6,
             self.center_buffer.instance_count(),
         )
@@ -32,6 +32,10 @@ mod point_light;
 #[doc(inline)]
 pub use point_light::*;
 
+mod shadow;
+#[doc(inline)]
+pub use shadow::*;
+
 mod ambient_light;
 #[doc(inline)]
 pub use ambient_light::*;
@@ -40,6 +44,10 @@ mod environment;
 #[doc(inline)]
 pub use environment::*;
 
+mod clustered_lights;
+#[doc(inline)]
+pub use clustered_lights::*;
+
 use crate::core::*;
 use crate::renderer::camera::*;
 
@@ -127,6 +135,7 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
 ///
 /// Returns shader source code with the function `calculate_lighting` which calculate the lighting contribution for the given lights and the given [LightingModel].
 /// Use this if you want to implement a custom [Material](crate::renderer::Material) but use the default lighting calculations.
+/// The generated source contains code for each of the given lights, so use [ClusteredLights] for scenes with many point and spot lights.
 ///
 /// The shader function has the following signature:
 /// ```no_rust
@@ -135,8 +144,8 @@ impl<T: Light> Light for std::sync::Arc<std::sync::RwLock<T>> {
 ///
 pub fn lights_shader_source(lights: &[&dyn Light], lighting_model: LightingModel) -> String {
     let mut shader_source = lighting_model_shader(lighting_model).to_string();
-    shader_source.push_str(include_str!("../core/shared.frag"));
-    shader_source.push_str(include_str!("light/shaders/light_shared.frag"));
+    shader_source.push_str("#include \"shared.frag\"\n");
+    shader_source.push_str("#include \"light_shared.frag\"\n");
     let mut dir_fun = String::new();
     for (i, light) in lights.iter().enumerate() {
         shader_source.push_str(&light.shader_source(i as u32));
@@ -175,8 +184,8 @@ fn compute_up_direction(direction: Vec3) -> Vec3 {
 use crate::renderer::{LightingModel, NormalDistributionFunction};
 pub(crate) fn lighting_model_shader(lighting_model: LightingModel) -> &'static str {
     match lighting_model {
-        LightingModel::Phong => "#define PHONG",
-        LightingModel::Blinn => "#define BLINN",
+        LightingModel::Phong => "#define PHONG\n",
+        LightingModel::Blinn => "#define BLINN\n",
         LightingModel::Cook(normal, _) => match normal {
             NormalDistributionFunction::Blinn => "#define COOK\n#define COOK_BLINN\n",
             NormalDistributionFunction::Beckmann => "#define COOK\n#define COOK_BECKMANN\n",
@@ -12,6 +12,8 @@ pub struct AmbientLight {
     pub color: Color,
     /// The light shining from the environment. This is calculated based on an environment map.
     pub environment: Option<Environment>,
+    /// Screen space ambient occlusion which is multiplied into the ambient lighting, see [ScreenSpaceAmbientOcclusion].
+    pub ambient_occlusion: Option<ScreenSpaceAmbientOcclusion>,
 }
 
 impl AmbientLight {
@@ -21,6 +23,7 @@ impl AmbientLight {
             intensity,
             color,
             environment: None,
+            ambient_occlusion: None,
         }
     }
 
@@ -35,13 +38,31 @@ impl AmbientLight {
             intensity,
             color,
             environment: Some(Environment::new(context, environment_map)),
+            ambient_occlusion: None,
         }
     }
 }
 
 impl Light for AmbientLight {
     fn shader_source(&self, i: u32) -> String {
-        if self.environment.is_some() {
+        let ambient_occlusion = if self.ambient_occlusion.is_some() {
+            "
+                uniform sampler2D ambientOcclusionTexture;
+                uniform vec4 ambientOcclusionViewport;
+                float screen_space_ambient_occlusion()
+                {
+                    return texture(ambientOcclusionTexture, (gl_FragCoord.xy - ambientOcclusionViewport.xy) / ambientOcclusionViewport.zw).r;
+                }
+            "
+        } else {
+            "
+                float screen_space_ambient_occlusion()
+                {
+                    return 1.0;
+                }
+            "
+        };
+        let lighting = if self.environment.is_some() {
             format!(
             "
                 uniform samplerCube irradianceMap;
@@ -51,6 +72,7 @@ impl Light for AmbientLight {
     
                 vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                 {{
+                    occlusion *= screen_space_ambient_occlusion();
                     vec3 N = normal;
                     vec3 V = view_direction;
                     vec3 R = reflect(-V, N); 
@@ -82,11 +104,13 @@ impl Light for AmbientLight {
                     uniform vec3 ambientColor;
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
+                        occlusion *= screen_space_ambient_occlusion();
                         return occlusion * ambientColor * mix(surface_color, vec3(0.0), metallic);
                     }}
                 
                 ", i)
-        }
+        };
+        format!("{}{}", ambient_occlusion, lighting)
     }
     fn use_uniforms(&self, program: &Program, _i: u32) {
         if let Some(ref environment) = self.environment {
@@ -94,14 +118,18 @@ impl Light for AmbientLight {
             program.use_texture_cube("prefilterMap", &environment.prefilter_map);
             program.use_texture("brdfLUT", &environment.brdf_map);
         }
+        if let Some(ref ambient_occlusion) = self.ambient_occlusion {
+            ambient_occlusion.use_uniforms(program);
+        }
         program.use_uniform("ambientColor", self.color.to_vec3() * self.intensity);
     }
 
     fn id(&self) -> u8 {
-        if self.environment.is_some() {
-            0b1u8 << 7
-        } else {
-            0b1u8 << 7 | 0b1u8
+        match (self.environment.is_some(), self.ambient_occlusion.is_some()) {
+            (true, false) => 0b1u8 << 7,
+            (false, false) => 0b1u8 << 7 | 0b1u8,
+            (true, true) => 0b1u8 << 7 | 0b1010u8,
+            (false, true) => 0b1u8 << 7 | 0b1011u8,
         }
     }
 }
@@ -112,6 +140,7 @@ impl Default for AmbientLight {
             color: Color::WHITE,
             intensity: 1.0,
             environment: None,
+            ambient_occlusion: None,
         }
     }
 }
@@ -0,0 +1,330 @@
+use crate::core::*;
+use crate::renderer::*;
+
+// The width of the texture containing the light indices of all clusters
+const INDEX_TEXTURE_WIDTH: u32 = 1024;
+
+// A light is ignored at the distance where its contribution is below this value
+const INTENSITY_THRESHOLD: f32 = 1.0 / 256.0;
+
+///
+/// A large number of [PointLight]s and [SpotLight]s which are evaluated in one fixed shader, independent of the number of lights, using clustered forward lighting (Forward+).
+/// Use this instead of passing each light to the render call when the scene contains many lights,
+/// since each light passed to the render call adds code to the shader and a change in the number of lights requires a new shader to be compiled.
+///
+/// The lights are packed into textures and divided into clusters, ie. cells of a grid which subdivides the view frustum of the camera, when calling [ClusteredLights::update].
+/// When shading a surface, only the lights in the cluster containing the surface are evaluated.
+/// Since this is a [Light], it works with all materials which use [lights_shader_source],
+/// for example [PhysicalMaterial] and [DeferredPhysicalMaterial], and it can be combined with other lights like an [AmbientLight] and a [DirectionalLight].
+///
+/// **Note:** The shadow maps of the lights are not used.
+///
+pub struct ClusteredLights {
+    context: Context,
+    light_texture: Texture2D,
+    cluster_texture: Texture2D,
+    index_texture: Texture2D,
+    view: Mat4,
+    view_projection: Mat4,
+    z_near: f32,
+    z_far: f32,
+    grid: (u32, u32, u32),
+    light_count: u32,
+    /// The number of clusters in the horizontal, vertical and depth direction of the view frustum.
+    /// More clusters means fewer lights evaluated per fragment but more work when calling [ClusteredLights::update].
+    pub cluster_count: (u32, u32, u32),
+}
+
+impl ClusteredLights {
+    ///
+    /// Constructs a new empty set of clustered lights. Use [ClusteredLights::update] to add lights.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            light_texture: new_data_texture::<[f32; 4]>(context, 4, 1),
+            cluster_texture: new_data_texture::<[f32; 2]>(context, 1, 1),
+            index_texture: new_data_texture::<f32>(context, INDEX_TEXTURE_WIDTH, 1),
+            view: Mat4::identity(),
+            view_projection: Mat4::identity(),
+            z_near: 0.01,
+            z_far: 1.0,
+            grid: (1, 1, 1),
+            light_count: 0,
+            cluster_count: (16, 9, 24),
+        }
+    }
+
+    ///
+    /// Updates the lights and assigns them to the clusters of the view frustum of the given camera.
+    /// This should be called each frame before rendering with the given camera, or at least when the camera or the lights have changed.
+    ///
+    pub fn update<'a>(
+        &mut self,
+        camera: &Camera,
+        point_lights: impl IntoIterator<Item = &'a PointLight>,
+        spot_lights: impl IntoIterator<Item = &'a SpotLight>,
+    ) {
+        let mut light_data: Vec<[f32; 4]> = Vec::new();
+        let mut spheres = Vec::new();
+        for light in point_lights {
+            let color = light.color.to_vec3() * light.intensity;
+            if let Some(range) = light_range(color, &light.attenuation) {
+                spheres.push((light.position, range));
+                light_data.extend::<[[f32; 4]; 4]>([
+                    light.position.extend(0.0).into(),
+                    color.extend(0.0).into(),
+                    attenuation_vector(&light.attenuation).extend(0.0).into(),
+                    [0.0, 0.0, 0.0, -1.0],
+                ]);
+            }
+        }
+        for light in spot_lights {
+            let color = light.color.to_vec3() * light.intensity;
+            if let Some(range) = light_range(color, &light.attenuation) {
+                spheres.push((light.position, range));
+                light_data.extend::<[[f32; 4]; 4]>([
+                    light.position.extend(0.0).into(),
+                    color.extend(0.0).into(),
+                    attenuation_vector(&light.attenuation).extend(0.0).into(),
+                    light.direction.normalize().extend(light.cutoff.0).into(),
+                ]);
+            }
+        }
+
+        let grid = (
+            self.cluster_count.0.max(1),
+            self.cluster_count.1.max(1),
+            self.cluster_count.2.max(1),
+        );
+        let z_near = camera.z_near().max(0.01);
+        let z_far = camera.z_far().max(z_near * 1.01);
+        let view = *camera.view();
+        let projection = camera.projection();
+        let slice = |depth: f32| {
+            ((depth.max(z_near) / z_near).ln() / (z_far / z_near).ln() * grid.2 as f32)
+                .clamp(0.0, grid.2 as f32 - 1.0) as u32
+        };
+        let tile = |ndc: f32, count: u32| {
+            ((ndc * 0.5 + 0.5) * count as f32).clamp(0.0, count as f32 - 1.0) as u32
+        };
+
+        let mut clusters = vec![Vec::new(); (grid.0 * grid.1 * grid.2) as usize];
+        for (index, (position, range)) in spheres.into_iter().enumerate() {
+            let center = (view * position.extend(1.0)).truncate();
+            let (min_depth, max_depth) = (-center.z - range, -center.z + range);
+            if max_depth <= 0.0 || min_depth > z_far {
+                continue;
+            }
+            let (mut x_range, mut y_range) = ((0, grid.0 - 1), (0, grid.1 - 1));
+            if min_depth > 0.0 && range.is_finite() {
+                let (mut min, mut max) = (vec2(f32::MAX, f32::MAX), vec2(f32::MIN, f32::MIN));
+                for corner in 0..8 {
+                    let offset = vec3(
+                        if corner & 1 == 0 { -range } else { range },
+                        if corner & 2 == 0 { -range } else { range },
+                        if corner & 4 == 0 { -range } else { range },
+                    );
+                    let clip = projection * (center + offset).extend(1.0);
+                    let ndc = clip.truncate().truncate() / clip.w;
+                    min = vec2(min.x.min(ndc.x), min.y.min(ndc.y));
+                    max = vec2(max.x.max(ndc.x), max.y.max(ndc.y));
+                }
+                if min.x > 1.0 || min.y > 1.0 || max.x < -1.0 || max.y < -1.0 {
+                    continue;
+                }
+                x_range = (tile(min.x, grid.0), tile(max.x, grid.0));
+                y_range = (tile(min.y, grid.1), tile(max.y, grid.1));
+            }
+            for z in slice(min_depth)..=slice(max_depth) {
+                for y in y_range.0..=y_range.1 {
+                    for x in x_range.0..=x_range.1 {
+                        clusters[((z * grid.1 + y) * grid.0 + x) as usize].push(index as f32);
+                    }
+                }
+            }
+        }
+
+        let mut cluster_data = Vec::with_capacity(clusters.len());
+        let mut index_data = Vec::new();
+        for cluster in clusters {
+            cluster_data.push([index_data.len() as f32, cluster.len() as f32]);
+            index_data.extend(cluster);
+        }
+
+        self.light_count = light_data.len() as u32 / 4;
+        fill_data_texture(&self.context, &mut self.light_texture, 4, light_data);
+        fill_data_texture(
+            &self.context,
+            &mut self.cluster_texture,
+            grid.0 * grid.1,
+            cluster_data,
+        );
+        fill_data_texture(
+            &self.context,
+            &mut self.index_texture,
+            INDEX_TEXTURE_WIDTH,
+            index_data,
+        );
+        self.view = view;
+        self.view_projection = projection * view;
+        self.z_near = z_near;
+        self.z_far = z_far;
+        self.grid = grid;
+    }
+
+    ///
+    /// Returns the number of lights which were added in the last call to [ClusteredLights::update], not including the lights that are too weak to affect anything.
+    ///
+    pub fn light_count(&self) -> u32 {
+        self.light_count
+    }
+}
+
+impl Light for ClusteredLights {
+    fn shader_source(&self, i: u32) -> String {
+        format!(
+        "
+            uniform sampler2D clusteredLights{};
+            uniform sampler2D clusteredClusters{};
+            uniform sampler2D clusteredIndices{};
+            uniform mat4 clusteredView{};
+            uniform mat4 clusteredViewProjection{};
+            uniform vec2 clusteredNearFar{};
+            uniform vec3 clusteredGrid{};
+
+            vec4 fetch_data{}(sampler2D data, int x, int y)
+            {{
+                return texelFetch(data, ivec2(x, textureSize(data, 0).y - 1 - y), 0);
+            }}
+
+            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+            {{
+                vec4 clip_position = clusteredViewProjection{} * vec4(position, 1.0);
+                vec2 tile = clamp((clip_position.xy / clip_position.w * 0.5 + 0.5) * clusteredGrid{}.xy, vec2(0.0), clusteredGrid{}.xy - 1.0);
+                float depth = max(-(clusteredView{} * vec4(position, 1.0)).z, clusteredNearFar{}.x);
+                float slice = clamp(log(depth / clusteredNearFar{}.x) / log(clusteredNearFar{}.y / clusteredNearFar{}.x) * clusteredGrid{}.z, 0.0, clusteredGrid{}.z - 1.0);
+                vec4 cluster = fetch_data{}(clusteredClusters{}, int(tile.y) * int(clusteredGrid{}.x) + int(tile.x), int(slice));
+                int offset = int(cluster.x);
+                int count = int(cluster.y);
+                int width = textureSize(clusteredIndices{}, 0).x;
+
+                vec3 color = vec3(0.0);
+                for (int j = 0; j < count; j++)
+                {{
+                    int light = int(fetch_data{}(clusteredIndices{}, (offset + j) % width, (offset + j) / width).x);
+                    vec3 light_position = fetch_data{}(clusteredLights{}, 0, light).xyz;
+                    vec3 light_color = fetch_data{}(clusteredLights{}, 1, light).xyz;
+                    vec3 attenuation = fetch_data{}(clusteredLights{}, 2, light).xyz;
+                    vec4 direction_cutoff = fetch_data{}(clusteredLights{}, 3, light);
+
+                    vec3 light_direction = light_position - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    float spot_factor = 1.0;
+                    if (direction_cutoff.w >= 0.0)
+                    {{
+                        float angle = acos(dot(-light_direction, direction_cutoff.xyz));
+                        float cutoff = direction_cutoff.w;
+                        spot_factor = angle < cutoff ? 1.0 - smoothstep(0.75 * cutoff, cutoff, angle) : 0.0;
+                    }}
+                    if (spot_factor > 0.0)
+                    {{
+                        color += calculate_light(attenuate(light_color, attenuation, distance), light_direction, surface_color, view_direction, normal, metallic, roughness) * spot_factor;
+                    }}
+                }}
+                return color;
+            }}
+
+        ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+    }
+
+    fn use_uniforms(&self, program: &Program, i: u32) {
+        program.use_texture(&format!("clusteredLights{}", i), &self.light_texture);
+        program.use_texture(&format!("clusteredClusters{}", i), &self.cluster_texture);
+        program.use_texture(&format!("clusteredIndices{}", i), &self.index_texture);
+        program.use_uniform(&format!("clusteredView{}", i), self.view);
+        program.use_uniform(
+            &format!("clusteredViewProjection{}", i),
+            self.view_projection,
+        );
+        program.use_uniform(
+            &format!("clusteredNearFar{}", i),
+            vec2(self.z_near, self.z_far),
+        );
+        program.use_uniform(
+            &format!("clusteredGrid{}", i),
+            vec3(self.grid.0 as f32, self.grid.1 as f32, self.grid.2 as f32),
+        );
+    }
+
+    fn id(&self) -> u8 {
+        0b1u8 << 7 | 0b1001u8
+    }
+}
+
+fn attenuation_vector(attenuation: &Attenuation) -> Vec3 {
+    vec3(
+        attenuation.constant,
+        attenuation.linear,
+        attenuation.quadratic,
+    )
+}
+
+///
+/// Returns the distance from a light with the given color and attenuation at which the contribution of the light is below [INTENSITY_THRESHOLD]
+/// or `None` if the contribution of the light is always below the threshold.
+///
+fn light_range(color: Vec3, attenuation: &Attenuation) -> Option<f32> {
+    let max = color.x.max(color.y).max(color.z) / INTENSITY_THRESHOLD;
+    if max <= 1.0 {
+        return None;
+    }
+    let (a, b, c) = (
+        attenuation.quadratic,
+        attenuation.linear,
+        attenuation.constant - max,
+    );
+    let range = if a > 0.0 {
+        (-b + (b * b - 4.0 * a * c).max(0.0).sqrt()) / (2.0 * a)
+    } else if b > 0.0 {
+        -c / b
+    } else if c < 0.0 {
+        f32::INFINITY
+    } else {
+        return None;
+    };
+    Some(range.max(0.0))
+}
+
+fn new_data_texture<T: TextureDataType>(context: &Context, width: u32, height: u32) -> Texture2D {
+    Texture2D::new_empty::<T>(
+        context,
+        width,
+        height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
+
+///
+/// Fills the texture with the given data, row by row, with the given texture width.
+/// The texture height is increased to the next power of two if the data does not fit, otherwise the texture is reused and the rest of it is filled with zeros.
+///
+fn fill_data_texture<T: TextureDataType + Default>(
+    context: &Context,
+    texture: &mut Texture2D,
+    width: u32,
+    mut data: Vec<T>,
+) {
+    let height = (data.len() as u32).div_ceil(width);
+    if texture.width() != width || texture.height() < height {
+        *texture = new_data_texture::<T>(context, width, height.max(1).next_power_of_two());
+    }
+    data.resize((texture.width() * texture.height()) as usize, T::default());
+    texture.fill(&data);
+}
@@ -8,14 +8,24 @@ use crate::renderer::*;
 ///
 pub struct DirectionalLight {
     context: Context,
-    shadow_texture: Option<DepthTexture2D>,
-    shadow_matrix: Mat4,
+    shadow: Option<ShadowMap>,
+    cascaded_shadow_texture: Option<DepthTexture2DArray>,
+    cascade_moments: Option<(Texture2DArray, ShadowFilter)>,
+    cascade_matrices: Vec<Mat4>,
+    cascade_splits: Vec<f32>,
+    cascade_view: Mat4,
+    cascade_blend_distance: f32,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
     pub color: Color,
     /// The direction the light shines.
     pub direction: Vec3,
+    /// The [ShadowSettings] used when calculating the shadow from this light.
+    pub shadow_settings: ShadowSettings,
+    /// A bitmask of the layers which cast shadows from this light. A geometry is only rendered into the shadow map if at least one of its layers (see [Geometry::layers]) is in this mask.
+    /// The default value contains all layers.
+    pub shadow_cull_mask: u32,
 }
 
 impl DirectionalLight {
@@ -28,11 +38,18 @@ impl DirectionalLight {
     ) -> DirectionalLight {
         DirectionalLight {
             context: context.clone(),
-            shadow_matrix: Mat4::identity(),
-            shadow_texture: None,
+            shadow: None,
+            cascaded_shadow_texture: None,
+            cascade_moments: None,
+            cascade_matrices: Vec::new(),
+            cascade_splits: Vec::new(),
+            cascade_view: Mat4::identity(),
+            cascade_blend_distance: 0.0,
             intensity,
             color,
             direction: *direction,
+            shadow_settings: ShadowSettings::default(),
+            shadow_cull_mask: u32::MAX,
         }
     }
 
@@ -41,12 +58,17 @@ impl DirectionalLight {
     /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [DirectionalLight::generate_shadow_map].
     ///
     pub fn clear_shadow_map(&mut self) {
-        self.shadow_texture = None;
-        self.shadow_matrix = Mat4::identity();
+        self.shadow = None;
+        self.cascaded_shadow_texture = None;
+        self.cascade_moments = None;
+        self.cascade_matrices.clear();
+        self.cascade_splits.clear();
     }
 
     ///
     /// Generate a shadow map which is used to simulate shadows from the directional light onto the geometries given as input.
+    /// Geometries which do not cast shadows (see [Geometry::cast_shadows]) or are not in the [DirectionalLight::shadow_cull_mask] are not rendered into the shadow map.
+    /// Any cascaded shadow map generated with [DirectionalLight::generate_cascaded_shadow_map] is replaced by this shadow map.
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size
     /// and/or split the scene by creating another light source with same parameters and let the two light sources shines on different parts of the scene.
@@ -71,7 +93,7 @@ impl DirectionalLight {
         let z_far = aabb.distance_max(&position);
         let z_near = aabb.distance(&position);
         let frustum_height = aabb.max().distance(aabb.min()); // TODO: more tight fit
-        let shadow_camera = Camera::new_orthographic(
+        let mut shadow_camera = Camera::new_orthographic(
             viewport,
             position,
             target,
@@ -80,67 +102,320 @@ impl DirectionalLight {
             z_near,
             z_far,
         );
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
+        shadow_camera.cull_mask = self.shadow_cull_mask;
+        let shadow = ShadowMap::new(
+            &self.context,
+            &shadow_camera,
+            texture_size,
+            &self.shadow_settings,
+            geometries,
+        );
+        self.clear_shadow_map();
+        self.shadow = Some(shadow);
+    }
+
+    ///
+    /// Returns a reference to the shadow map if it has been generated.
+    ///
+    pub fn shadow_map(&self) -> Option<&DepthTexture2D> {
+        self.shadow.as_ref().map(|shadow| &shadow.texture)
+    }
+
+    ///
+    /// Generate a cascaded shadow map which is used to simulate shadows from the directional light onto the geometries given as input.
+    /// Geometries which do not cast shadows (see [Geometry::cast_shadows]) or are not in the [DirectionalLight::shadow_cull_mask] are not rendered into the shadow map.
+    /// The view frustum of the given camera is split into a number of slices (see [ShadowCascades]) and each slice is rendered into one layer of a [DepthTexture2DArray],
+    /// which means that the shadows close to the camera get a much higher resolution than when using [DirectionalLight::generate_shadow_map].
+    /// The cascaded shadow map is only valid for the given camera, so it should be regenerated whenever the camera moves.
+    /// Any shadow map generated with [DirectionalLight::generate_shadow_map] is replaced by this shadow map.
+    /// The [ShadowSettings] are applied to each cascade, so for example the variance shadow map filters generate a blurred moment texture for each cascade.
+    ///
+    pub fn generate_cascaded_shadow_map(
+        &mut self,
+        camera: &Camera,
+        texture_size: u32,
+        cascades: ShadowCascades,
+        geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        let cascade_count = cascades.cascade_count.clamp(1, MAX_SHADOW_CASCADES);
+        let up = compute_up_direction(self.direction);
+        let direction = self.direction.normalize();
+
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+        let mut aabb = AxisAlignedBoundingBox::EMPTY;
+        for geometry in geometries.clone() {
+            aabb.expand_with_aabb(&geometry.aabb());
+        }
+        if aabb.is_empty() {
+            return;
+        }
+        let scene_size = aabb.max().distance(aabb.min());
+
+        let z_near = camera.z_near();
+        let z_far = cascades
+            .max_distance
+            .map(|d| d.min(camera.z_far()))
+            .unwrap_or_else(|| camera.z_far());
+        let view_projection_inverse = (camera.projection() * camera.view()).invert().unwrap();
+        let ndc_depth = |distance: f32| {
+            let clip = camera.projection() * vec4(0.0, 0.0, -distance, 1.0);
+            clip.z / clip.w
+        };
+
+        let mut splits = Vec::with_capacity(cascade_count as usize);
+        for i in 1..=cascade_count {
+            let p = i as f32 / cascade_count as f32;
+            let logarithmic = z_near.max(0.001) * (z_far / z_near.max(0.001)).powf(p);
+            let uniform = z_near + (z_far - z_near) * p;
+            splits.push(
+                cascades.split_lambda * logarithmic + (1.0 - cascades.split_lambda) * uniform,
+            );
+        }
+
+        let mut shadow_texture = DepthTexture2DArray::new::<f32>(
             &self.context,
             texture_size,
             texture_size,
+            cascade_count,
             Wrapping::ClampToEdge,
             Wrapping::ClampToEdge,
         );
+        let filter = self.shadow_settings.filter;
+        let mut moments = match filter {
+            ShadowFilter::Vsm { .. } | ShadowFilter::Evsm { .. } => Some((
+                Texture2DArray::new_empty::<[f32; 4]>(
+                    &self.context,
+                    texture_size,
+                    texture_size,
+                    cascade_count,
+                    Interpolation::Linear,
+                    Interpolation::Linear,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                DepthTexture2D::new::<f32>(
+                    &self.context,
+                    texture_size,
+                    texture_size,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            )),
+            _ => None,
+        };
         let depth_material = DepthMaterial {
             render_states: RenderStates {
                 write_mask: WriteMask::DEPTH,
+                depth_bias: self.shadow_settings.polygon_offset,
                 ..Default::default()
             },
             ..Default::default()
         };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
+        let mut matrices = Vec::with_capacity(cascade_count as usize);
+        let mut slice_near = z_near;
+        for (layer, slice_far) in splits.iter().enumerate() {
+            let near_depth = ndc_depth(slice_near);
+            let far_depth = ndc_depth(*slice_far);
+            let mut corners = Vec::with_capacity(8);
+            for z in [near_depth, far_depth] {
+                for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    let corner = view_projection_inverse * vec4(x, y, z, 1.0);
+                    corners.push(corner.truncate() / corner.w);
                 }
-            });
-        self.shadow_texture = Some(shadow_texture);
-        self.shadow_matrix = shadow_matrix(&shadow_camera);
+            }
+            let mut center = corners.iter().fold(vec3(0.0, 0.0, 0.0), |acc, c| acc + *c) / 8.0;
+            let radius = corners
+                .iter()
+                .map(|c| c.distance(center))
+                .fold(0.0f32, |a, b| a.max(b));
+
+            // Snap the center to the texel grid to avoid shimmering shadow edges when the camera moves
+            let texel_size = 2.0 * radius / texture_size as f32;
+            let right = direction.cross(up).normalize();
+            let light_up = right.cross(direction).normalize();
+            let x = (center.dot(right) / texel_size).floor() * texel_size;
+            let y = (center.dot(light_up) / texel_size).floor() * texel_size;
+            center += right * (x - center.dot(right)) + light_up * (y - center.dot(light_up));
+
+            let back = radius + scene_size;
+            let position = center - back * direction;
+            let mut shadow_camera = Camera::new_orthographic(
+                viewport,
+                position,
+                center,
+                light_up,
+                2.0 * radius,
+                0.0,
+                back + radius,
+            );
+            shadow_camera.cull_mask = self.shadow_cull_mask;
+            let casters = geometries
+                .clone()
+                .into_iter()
+                .filter(|g| g.cast_shadows() && is_visible(&shadow_camera, g));
+            if let Some((ref mut moment_textures, ref mut depth_texture)) = moments {
+                let moment_texture = render_moment_texture(
+                    &self.context,
+                    &shadow_camera,
+                    depth_texture,
+                    &self.shadow_settings,
+                    casters,
+                );
+                moment_textures
+                    .as_color_target(&[layer as u32], None)
+                    .copy_from(
+                        ColorTexture::Single(&moment_texture),
+                        viewport,
+                        WriteMask::COLOR,
+                    );
+                shadow_texture
+                    .as_depth_target(layer as u32)
+                    .copy_from(DepthTexture::Single(depth_texture), viewport);
+            } else {
+                shadow_texture
+                    .as_depth_target(layer as u32)
+                    .clear(ClearState::default())
+                    .write(|| {
+                        for geometry in casters {
+                            render_with_material(
+                                &self.context,
+                                &shadow_camera,
+                                &geometry,
+                                &depth_material,
+                                &[],
+                            );
+                        }
+                    });
+            }
+            matrices.push(shadow_matrix(&shadow_camera));
+            slice_near = *slice_far;
+        }
+        self.clear_shadow_map();
+        self.cascaded_shadow_texture = Some(shadow_texture);
+        self.cascade_moments = moments.map(|(moment_textures, _)| (moment_textures, filter));
+        self.cascade_matrices = matrices;
+        self.cascade_splits = splits;
+        self.cascade_view = *camera.view();
+        self.cascade_blend_distance = cascades.blend_distance.clamp(0.0, 1.0);
     }
 
     ///
-    /// Returns a reference to the shadow map if it has been generated.
+    /// Returns a reference to the cascaded shadow map if it has been generated.
     ///
-    pub fn shadow_map(&self) -> Option<&DepthTexture2D> {
-        self.shadow_texture.as_ref()
+    pub fn cascaded_shadow_map(&self) -> Option<&DepthTexture2DArray> {
+        self.cascaded_shadow_texture.as_ref()
+    }
+
+    fn cascade_filter(&self) -> ShadowFilter {
+        self.shadow_settings
+            .filter
+            .effective(self.cascade_moments.as_ref().map(|(_, filter)| *filter))
+    }
+}
+
+/// The maximum number of cascades in a cascaded shadow map, see [DirectionalLight::generate_cascaded_shadow_map].
+pub const MAX_SHADOW_CASCADES: u32 = 8;
+
+///
+/// Settings for splitting the view frustum of a camera into a number of cascades, see [DirectionalLight::generate_cascaded_shadow_map].
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowCascades {
+    /// The number of cascades, ie. the number of slices the view frustum is split into. Must be between 1 and [MAX_SHADOW_CASCADES].
+    pub cascade_count: u32,
+    /// A value in the range `[0..1]` which determines how the view frustum is split.
+    /// A value of 0 splits the frustum uniformly while a value of 1 splits it logarithmically, which gives higher resolution close to the camera.
+    pub split_lambda: f32,
+    /// The maximum distance from the camera that receives shadows. If None, then the far plane of the camera is used.
+    pub max_distance: Option<f32>,
+    /// A value in the range `[0..1]` specifying the fraction of each cascade that is blended with the next cascade to hide the transition.
+    /// A value of 0 means no blending.
+    pub blend_distance: f32,
+}
+
+impl Default for ShadowCascades {
+    fn default() -> Self {
+        Self {
+            cascade_count: 4,
+            split_lambda: 0.75,
+            max_distance: None,
+            blend_distance: 0.1,
+        }
     }
 }
 
 impl Light for DirectionalLight {
     fn shader_source(&self, i: u32) -> String {
-        if self.shadow_texture.is_some() {
+        if self.cascaded_shadow_texture.is_some() {
+            let lookup = self.cascade_filter().lookup_source(
+                &format!("shadowMaps{}", i),
+                Some("cascade"),
+                i,
+            );
             format!(
                 "
-                    uniform sampler2D shadowMap{};
-                    uniform mat4 shadowMVP{};
-        
+                    uniform sampler2DArray shadowMaps{};
+                    uniform mat4 shadowMVPs{}[{}];
+                    uniform float cascadeSplits{}[{}];
+                    uniform int cascadeCount{};
+                    uniform float cascadeBlend{};
+                    uniform mat4 cascadeView{};
+                    uniform vec3 shadowBias{};
+                    uniform int shadowKernelSize{};
+                    uniform vec4 shadowFilter{};
+
+                    float calculate_cascade_shadow{}(int cascade, vec3 position, vec3 normal, vec3 light_direction)
+                    {{
+                        vec3 shadow_coord = shadow_coordinate(shadowMVPs{}[cascade], shadowBias{}, position, normal, light_direction);
+                        return {};
+                    }}
+
+                    float calculate_cascaded_shadow{}(vec3 position, vec3 normal, vec3 light_direction)
+                    {{
+                        if (ignoreShadows != 0) {{
+                            return 1.0;
+                        }}
+                        vec2 cascade = select_cascade(cascadeSplits{}, cascadeCount{}, cascadeBlend{}, cascadeView{}, position);
+                        if (cascade.x < 0.0) {{
+                            return 1.0;
+                        }}
+                        int index = int(cascade.x);
+                        float visibility = calculate_cascade_shadow{}(index, position, normal, light_direction);
+                        if (cascade.y > 0.0) {{
+                            float next_visibility = index + 1 < cascadeCount{} ? calculate_cascade_shadow{}(index + 1, position, normal, light_direction) : 1.0;
+                            visibility = mix(visibility, next_visibility, cascade.y);
+                        }}
+                        return visibility;
+                    }}
+
+                    uniform vec3 color{};
+                    uniform vec3 direction{};
+
+                    vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                    {{
+                        return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness)
+                            * calculate_cascaded_shadow{}(position, normal, -direction{});
+                    }}
+
+                ", i, i, MAX_SHADOW_CASCADES, i, MAX_SHADOW_CASCADES, i, i, i, i, i, i,
+                i, i, i, lookup,
+                i, i, i, i, i, i, i, i,
+                i, i, i, i, i, i, i)
+        } else if let Some(ref shadow) = self.shadow {
+            format!(
+                "
+                    {}
                     uniform vec3 color{};
                     uniform vec3 direction{};
         
                     vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
                     {{
                         return calculate_light(color{}, -direction{}, surface_color, view_direction, normal, metallic, roughness) 
-                            * calculate_shadow(shadowMap{}, shadowMVP{}, position);
+                            * calculate_shadow{}(position, normal, -direction{});
                     }}
                 
-                ", i, i, i, i, i, i, i, i, i)
+                ", shadow.shader_source(&self.shadow_settings, i), i, i, i, i, i, i, i)
         } else {
             format!(
                 "
@@ -156,9 +431,45 @@ impl Light for DirectionalLight {
         }
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
-        if let Some(ref tex) = self.shadow_texture {
-            program.use_depth_texture(&format!("shadowMap{}", i), tex);
-            program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+        if let Some(ref shadow) = self.shadow {
+            shadow.use_uniforms(program, &self.shadow_settings, i);
+        }
+        if let Some(ref tex) = self.cascaded_shadow_texture {
+            program.use_uniform_if_required("ignoreShadows", 0i32);
+            let mut matrices = [Mat4::identity(); MAX_SHADOW_CASCADES as usize];
+            matrices[..self.cascade_matrices.len()].copy_from_slice(&self.cascade_matrices);
+            let mut splits = [f32::MAX; MAX_SHADOW_CASCADES as usize];
+            splits[..self.cascade_splits.len()].copy_from_slice(&self.cascade_splits);
+            let filter = self.cascade_filter();
+            match self.cascade_moments {
+                Some((ref moment_textures, _))
+                    if matches!(filter, ShadowFilter::Vsm { .. } | ShadowFilter::Evsm { .. }) =>
+                {
+                    program.use_texture_array(&format!("shadowMaps{}", i), moment_textures)
+                }
+                _ => program.use_depth_texture_array(&format!("shadowMaps{}", i), tex),
+            }
+            program.use_uniform_array(&format!("shadowMVPs{}", i), &matrices);
+            program.use_uniform_array(&format!("cascadeSplits{}", i), &splits);
+            program.use_uniform(
+                &format!("cascadeCount{}", i),
+                self.cascade_matrices.len() as i32,
+            );
+            program.use_uniform(&format!("cascadeBlend{}", i), self.cascade_blend_distance);
+            program.use_uniform(&format!("cascadeView{}", i), self.cascade_view);
+            program.use_uniform(
+                &format!("shadowBias{}", i),
+                vec3(
+                    self.shadow_settings.depth_bias,
+                    self.shadow_settings.slope_bias,
+                    self.shadow_settings.normal_offset,
+                ),
+            );
+            program.use_uniform_if_required(
+                &format!("shadowKernelSize{}", i),
+                filter.kernel_size() as i32,
+            );
+            program.use_uniform_if_required(&format!("shadowFilter{}", i), filter.parameters());
         }
         program.use_uniform(
             &format!("color{}", i),
@@ -168,8 +479,10 @@ impl Light for DirectionalLight {
     }
 
     fn id(&self) -> u8 {
-        if self.shadow_texture.is_some() {
-            0b1u8 << 7 | 0b10u8
+        if self.cascaded_shadow_texture.is_some() {
+            0b1u8 << 7 | self.cascade_filter().id() << 4 | 0b111u8
+        } else if let Some(ref shadow) = self.shadow {
+            0b1u8 << 7 | shadow.id(&self.shadow_settings) << 4 | 0b10u8
         } else {
             0b1u8 << 7 | 0b11u8
         }
@@ -149,8 +149,8 @@ impl Material for PrefilterMaterial<'_> {
         format!(
             "{}{}{}{}",
             super::lighting_model_shader(self.lighting_model),
-            include_str!("../../core/shared.frag"),
-            include_str!("shaders/light_shared.frag"),
+            "#include \"shared.frag\"\n",
+            "#include \"light_shared.frag\"\n",
             include_str!("shaders/prefilter.frag")
         )
     }
@@ -195,8 +195,8 @@ impl Material for BrdfMaterial {
         format!(
             "{}{}{}{}",
             super::lighting_model_shader(self.lighting_model),
-            include_str!("../../core/shared.frag"),
-            include_str!("shaders/light_shared.frag"),
+            "#include \"shared.frag\"\n",
+            "#include \"light_shared.frag\"\n",
             include_str!("shaders/brdf.frag")
         )
     }
@@ -232,7 +232,7 @@ impl Material for IrradianceMaterial<'_> {
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
         format!(
             "{}{}",
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             include_str!("shaders/irradiance.frag")
         )
     }
@@ -3,8 +3,13 @@ use crate::renderer::*;
 
 ///
 /// A light which shines from the given position in all directions.
+/// The light will cast shadows if you [generate a shadow map](PointLight::generate_shadow_map).
 ///
 pub struct PointLight {
+    context: Context,
+    shadow_texture: Option<DepthTextureCubeMap>,
+    shadow_z_near: f32,
+    shadow_z_far: f32,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -13,47 +18,180 @@ pub struct PointLight {
     pub position: Vec3,
     /// The [Attenuation] of the light.
     pub attenuation: Attenuation,
+    /// A bitmask of the layers which cast shadows from this light. A geometry is only rendered into the shadow map if at least one of its layers (see [Geometry::layers]) is in this mask.
+    /// The default value contains all layers.
+    pub shadow_cull_mask: u32,
 }
 
 impl PointLight {
     /// Constructs a new point light.
     pub fn new(
-        _context: &Context,
+        context: &Context,
         intensity: f32,
         color: Color,
         position: &Vec3,
         attenuation: Attenuation,
     ) -> PointLight {
         PointLight {
+            context: context.clone(),
+            shadow_texture: None,
+            shadow_z_near: 0.0,
+            shadow_z_far: 0.0,
             intensity,
             color,
             position: *position,
             attenuation,
+            shadow_cull_mask: u32::MAX,
         }
     }
+
+    ///
+    /// Clear the shadow map, effectively disable the shadow.
+    /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [PointLight::generate_shadow_map].
+    ///
+    pub fn clear_shadow_map(&mut self) {
+        self.shadow_texture = None;
+    }
+
+    ///
+    /// Generate a shadow map which is used to simulate shadows from the point light onto the geometries given as input.
+    /// Geometries which do not cast shadows (see [Geometry::cast_shadows]) or are not in the [PointLight::shadow_cull_mask] are not rendered into the shadow map.
+    /// The scene is rendered in all six directions from the light position into the sides of a [DepthTextureCubeMap].
+    /// It is recomended that the texture size is power of 2.
+    /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
+    ///
+    pub fn generate_shadow_map(
+        &mut self,
+        texture_size: u32,
+        geometries: impl IntoIterator<Item = impl Geometry> + Clone,
+    ) {
+        let viewport = Viewport::new_at_origo(texture_size, texture_size);
+
+        let mut z_far = 0.0f32;
+        let mut z_near = f32::MAX;
+        for geometry in geometries.clone() {
+            let aabb = geometry.aabb();
+            if !aabb.is_empty() {
+                z_far = z_far.max(aabb.distance_max(&self.position));
+                z_near = z_near.min(aabb.distance(&self.position));
+            }
+        }
+        if z_far <= 0.0 {
+            return;
+        }
+        let z_near = z_near.max(0.01);
+
+        let mut shadow_texture = DepthTextureCubeMap::new::<f32>(
+            &self.context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let depth_material = DepthMaterial {
+            render_states: RenderStates {
+                write_mask: WriteMask::DEPTH,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for side in CubeMapSide::iter() {
+            let mut shadow_camera = Camera::new_perspective(
+                viewport,
+                self.position,
+                self.position + side.direction(),
+                side.up(),
+                degrees(90.0),
+                z_near,
+                z_far,
+            );
+            shadow_camera.cull_mask = self.shadow_cull_mask;
+            shadow_texture
+                .as_depth_target(side)
+                .clear(ClearState::default())
+                .write(|| {
+                    for geometry in geometries
+                        .clone()
+                        .into_iter()
+                        .filter(|g| g.cast_shadows() && is_visible(&shadow_camera, g))
+                    {
+                        render_with_material(
+                            &self.context,
+                            &shadow_camera,
+                            &geometry,
+                            &depth_material,
+                            &[],
+                        );
+                    }
+                });
+        }
+        self.shadow_texture = Some(shadow_texture);
+        self.shadow_z_near = z_near;
+        self.shadow_z_far = z_far;
+    }
+
+    ///
+    /// Returns a reference to the shadow map if it has been generated.
+    ///
+    pub fn shadow_map(&self) -> Option<&DepthTextureCubeMap> {
+        self.shadow_texture.as_ref()
+    }
 }
 
 impl Light for PointLight {
     fn shader_source(&self, i: u32) -> String {
-        format!(
-        "
-            uniform vec3 color{};
-            uniform vec3 attenuation{};
-            uniform vec3 position{};
-
-            vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
-            {{
-                vec3 light_direction = position{} - position;
-                float distance = length(light_direction);
-                light_direction = light_direction / distance;
-
-                vec3 light_color = attenuate(color{}, attenuation{}, distance);
-                return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
-            }}
-        
-        ", i, i, i, i, i, i, i)
+        if self.shadow_texture.is_some() {
+            format!(
+            "
+                uniform samplerCube shadowMap{};
+                uniform vec2 shadowNearFar{};
+
+                uniform vec3 color{};
+                uniform vec3 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness)
+                        * calculate_omnidirectional_shadow(shadowMap{}, shadowNearFar{}, position{}, position);
+                }}
+
+            ", i, i, i, i, i, i, i, i, i, i, i, i)
+        } else {
+            format!(
+            "
+                uniform vec3 color{};
+                uniform vec3 attenuation{};
+                uniform vec3 position{};
+
+                vec3 calculate_lighting{}(vec3 surface_color, vec3 position, vec3 normal, vec3 view_direction, float metallic, float roughness, float occlusion)
+                {{
+                    vec3 light_direction = position{} - position;
+                    float distance = length(light_direction);
+                    light_direction = light_direction / distance;
+
+                    vec3 light_color = attenuate(color{}, attenuation{}, distance);
+                    return calculate_light(light_color, light_direction, surface_color, view_direction, normal, metallic, roughness);
+                }}
+
+            ", i, i, i, i, i, i, i)
+        }
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
+        if let Some(ref tex) = self.shadow_texture {
+            program.use_uniform_if_required("ignoreShadows", 0i32);
+            program.use_depth_texture_cube(&format!("shadowMap{}", i), tex);
+            program.use_uniform(
+                &format!("shadowNearFar{}", i),
+                vec2(self.shadow_z_near, self.shadow_z_far),
+            );
+        }
         program.use_uniform(
             &format!("color{}", i),
             self.color.to_vec3() * self.intensity,
@@ -70,6 +208,10 @@ impl Light for PointLight {
     }
 
     fn id(&self) -> u8 {
-        0b1u8 << 7 | 0b100u8
+        if self.shadow_texture.is_some() {
+            0b1u8 << 7 | 0b1000u8
+        } else {
+            0b1u8 << 7 | 0b100u8
+        }
     }
 }
@@ -147,9 +147,25 @@ vec3 attenuate(vec3 light_color, vec3 attenuation, float distance)
     return light_color / max(1.0, att);
 }
 
-float is_visible(sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
+// Set to a non-zero value when rendering an object which does not receive shadows
+uniform int ignoreShadows;
+
+float shadow_bias(vec3 shadowBias, vec3 normal, vec3 light_direction)
+{
+    float cos_angle = clamp(dot(normal, light_direction), 0.0, 1.0);
+    float tan_angle = sqrt(1.0 - cos_angle * cos_angle) / max(cos_angle, 0.1);
+    return shadowBias.x + shadowBias.y * tan_angle;
+}
+
+// shadowBias contains the depth bias, the slope bias and the normal offset
+vec3 shadow_coordinate(mat4 shadowMVP, vec3 shadowBias, vec3 position, vec3 normal, vec3 light_direction)
+{
+    vec4 shadow_coord = shadowMVP * vec4(position + shadowBias.z * normal, 1.);
+    return vec3(shadow_coord.xy, shadow_coord.z - shadow_bias(shadowBias, normal, light_direction)) / shadow_coord.w;
+}
+
+float is_visible(sampler2D shadowMap, vec2 uv, float depth)
 {
-    vec2 uv = (shadow_coord.xy + offset)/shadow_coord.w;
     if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
         return 1.0;
     }
@@ -157,23 +173,246 @@ float is_visible(sampler2D shadowMap, vec4 shadow_coord, vec2 offset)
     if(shadow_cast_distance > 0.999) {
         return 1.0;
     }
-    float true_distance = (shadow_coord.z - 0.005)/shadow_coord.w;
-    return shadow_cast_distance > true_distance ? 1.0 : 0.0;
+    return shadow_cast_distance > depth ? 1.0 : 0.0;
+}
+
+float calculate_shadow_pcf(sampler2D shadowMap, vec3 shadow_coord, int kernel_size, vec2 sample_step)
+{
+    float center = 0.5 * float(kernel_size - 1);
+    float visibility = 0.0;
+    for (int x = 0; x < kernel_size; x++)
+    {
+        for (int y = 0; y < kernel_size; y++)
+        {
+            vec2 offset = (vec2(float(x), float(y)) - center) * sample_step;
+            visibility += is_visible(shadowMap, shadow_coord.xy + offset, shadow_coord.z);
+        }
+    }
+    return visibility / float(kernel_size * kernel_size);
+}
+
+float calculate_shadow_pcss(sampler2D shadowMap, vec3 shadow_coord, int kernel_size, float light_size)
+{
+    float center = 0.5 * float(kernel_size - 1);
+    float sample_step = light_size / max(float(kernel_size - 1), 1.0);
+
+    // Find the average depth of the occluders within the light size
+    float blocker_depth = 0.0;
+    float blocker_count = 0.0;
+    for (int x = 0; x < kernel_size; x++)
+    {
+        for (int y = 0; y < kernel_size; y++)
+        {
+            vec2 uv = shadow_coord.xy + (vec2(float(x), float(y)) - center) * sample_step;
+            if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+                continue;
+            }
+            float depth = texture(shadowMap, uv).x;
+            if (depth < shadow_coord.z) {
+                blocker_depth += depth;
+                blocker_count += 1.0;
+            }
+        }
+    }
+    if (blocker_count < 0.5) {
+        return 1.0;
+    }
+    blocker_depth /= blocker_count;
+
+    // The penumbra size is estimated using similar triangles
+    float penumbra = light_size * (shadow_coord.z - blocker_depth) / max(blocker_depth, 0.0001);
+    vec2 texel_size = 1.0 / vec2(textureSize(shadowMap, 0));
+    vec2 filter_step = max(vec2(penumbra / max(float(kernel_size - 1), 1.0)), texel_size);
+    return calculate_shadow_pcf(shadowMap, shadow_coord, kernel_size, filter_step);
+}
+
+float chebyshev_upper_bound(vec2 moments, float depth, float min_variance, float light_bleeding_reduction)
+{
+    if (depth <= moments.x) {
+        return 1.0;
+    }
+    float variance = max(moments.y - moments.x * moments.x, min_variance);
+    float d = depth - moments.x;
+    float p_max = variance / (variance + d * d);
+    return clamp((p_max - light_bleeding_reduction) / (1.0 - light_bleeding_reduction), 0.0, 1.0);
+}
+
+float vsm_visibility(vec2 moments, float depth, float light_bleeding_reduction)
+{
+    return chebyshev_upper_bound(moments, depth, 0.00002, light_bleeding_reduction);
+}
+
+float evsm_visibility(vec4 moments, float depth, float light_bleeding_reduction, vec2 exponents)
+{
+    depth = 2.0 * depth - 1.0;
+    float positive = exp(exponents.x * depth);
+    float negative = -exp(-exponents.y * depth);
+    float positive_min_variance = 0.0001 * exponents.x * positive * exponents.x * positive;
+    float negative_min_variance = 0.0001 * exponents.y * negative * exponents.y * negative;
+    float positive_visibility = chebyshev_upper_bound(moments.xy, positive, positive_min_variance, light_bleeding_reduction);
+    float negative_visibility = chebyshev_upper_bound(moments.zw, negative, negative_min_variance, light_bleeding_reduction);
+    return min(positive_visibility, negative_visibility);
+}
+
+float calculate_shadow_vsm(sampler2D momentMap, vec3 shadow_coord, float light_bleeding_reduction)
+{
+    if(shadow_coord.x < 0.0 || shadow_coord.x > 1.0 || shadow_coord.y < 0.0 || shadow_coord.y > 1.0) {
+        return 1.0;
+    }
+    return vsm_visibility(texture(momentMap, shadow_coord.xy).xy, shadow_coord.z, light_bleeding_reduction);
+}
+
+float calculate_shadow_evsm(sampler2D momentMap, vec3 shadow_coord, float light_bleeding_reduction, vec2 exponents)
+{
+    if(shadow_coord.x < 0.0 || shadow_coord.x > 1.0 || shadow_coord.y < 0.0 || shadow_coord.y > 1.0) {
+        return 1.0;
+    }
+    return evsm_visibility(texture(momentMap, shadow_coord.xy), shadow_coord.z, light_bleeding_reduction, exponents);
+}
+
+// The same filters as above for a layer of a shadow map array, used for cascaded shadow maps
+
+float is_visible(sampler2DArray shadowMap, int layer, vec2 uv, float depth)
+{
+    if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        return 1.0;
+    }
+    float shadow_cast_distance = texture(shadowMap, vec3(uv, float(layer))).x;
+    if(shadow_cast_distance > 0.999) {
+        return 1.0;
+    }
+    return shadow_cast_distance > depth ? 1.0 : 0.0;
 }
 
-float calculate_shadow(sampler2D shadowMap, mat4 shadowMVP, vec3 position)
+float calculate_shadow_pcf(sampler2DArray shadowMap, int layer, vec3 shadow_coord, int kernel_size, vec2 sample_step)
 {
-    vec4 shadow_coord = shadowMVP * vec4(position, 1.);
+    float center = 0.5 * float(kernel_size - 1);
     float visibility = 0.0;
+    for (int x = 0; x < kernel_size; x++)
+    {
+        for (int y = 0; y < kernel_size; y++)
+        {
+            vec2 offset = (vec2(float(x), float(y)) - center) * sample_step;
+            visibility += is_visible(shadowMap, layer, shadow_coord.xy + offset, shadow_coord.z);
+        }
+    }
+    return visibility / float(kernel_size * kernel_size);
+}
+
+float calculate_shadow_pcss(sampler2DArray shadowMap, int layer, vec3 shadow_coord, int kernel_size, float light_size)
+{
+    float center = 0.5 * float(kernel_size - 1);
+    float sample_step = light_size / max(float(kernel_size - 1), 1.0);
+
+    // Find the average depth of the occluders within the light size
+    float blocker_depth = 0.0;
+    float blocker_count = 0.0;
+    for (int x = 0; x < kernel_size; x++)
+    {
+        for (int y = 0; y < kernel_size; y++)
+        {
+            vec2 uv = shadow_coord.xy + (vec2(float(x), float(y)) - center) * sample_step;
+            if(uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+                continue;
+            }
+            float depth = texture(shadowMap, vec3(uv, float(layer))).x;
+            if (depth < shadow_coord.z) {
+                blocker_depth += depth;
+                blocker_count += 1.0;
+            }
+        }
+    }
+    if (blocker_count < 0.5) {
+        return 1.0;
+    }
+    blocker_depth /= blocker_count;
+
+    // The penumbra size is estimated using similar triangles
+    float penumbra = light_size * (shadow_coord.z - blocker_depth) / max(blocker_depth, 0.0001);
+    vec2 texel_size = 1.0 / vec2(textureSize(shadowMap, 0).xy);
+    vec2 filter_step = max(vec2(penumbra / max(float(kernel_size - 1), 1.0)), texel_size);
+    return calculate_shadow_pcf(shadowMap, layer, shadow_coord, kernel_size, filter_step);
+}
+
+float calculate_shadow_vsm(sampler2DArray momentMap, int layer, vec3 shadow_coord, float light_bleeding_reduction)
+{
+    if(shadow_coord.x < 0.0 || shadow_coord.x > 1.0 || shadow_coord.y < 0.0 || shadow_coord.y > 1.0) {
+        return 1.0;
+    }
+    return vsm_visibility(texture(momentMap, vec3(shadow_coord.xy, float(layer))).xy, shadow_coord.z, light_bleeding_reduction);
+}
+
+float calculate_shadow_evsm(sampler2DArray momentMap, int layer, vec3 shadow_coord, float light_bleeding_reduction, vec2 exponents)
+{
+    if(shadow_coord.x < 0.0 || shadow_coord.x > 1.0 || shadow_coord.y < 0.0 || shadow_coord.y > 1.0) {
+        return 1.0;
+    }
+    return evsm_visibility(texture(momentMap, vec3(shadow_coord.xy, float(layer))), shadow_coord.z, light_bleeding_reduction, exponents);
+}
+
+// Returns the cascade which contains the position in x, or -1 if the position is outside all cascades,
+// and the amount the shadow should be blended with the next cascade to hide the transition in y.
+// The array size must match MAX_SHADOW_CASCADES
+vec2 select_cascade(float cascadeSplits[8], int cascadeCount, float cascadeBlend, mat4 cascadeView, vec3 position)
+{
+    float depth = -(cascadeView * vec4(position, 1.0)).z;
+    int cascade = cascadeCount;
+    for (int i = 0; i < cascadeCount; i++)
+    {
+        if (depth < cascadeSplits[i]) {
+            cascade = i;
+            break;
+        }
+    }
+    if (cascade >= cascadeCount) {
+        return vec2(-1.0, 0.0);
+    }
+
+    // Blend with the next cascade close to the far end of this cascade
+    float cascade_near = cascade > 0 ? cascadeSplits[cascade - 1] : 0.0;
+    float cascade_far = cascadeSplits[cascade];
+    float blend_start = cascade_far - cascadeBlend * (cascade_far - cascade_near);
+    float blend = cascadeBlend > 0.0 ? smoothstep(blend_start, cascade_far, depth) : 0.0;
+    return vec2(float(cascade), blend);
+}
+
+float is_visible_in_cube(samplerCube shadowMap, vec3 direction, float true_distance)
+{
+    float shadow_cast_distance = texture(shadowMap, direction).x;
+    if(shadow_cast_distance > 0.999) {
+        return 1.0;
+    }
+    return shadow_cast_distance > true_distance ? 1.0 : 0.0;
+}
+
+float calculate_omnidirectional_shadow(samplerCube shadowMap, vec2 shadowNearFar, vec3 light_position, vec3 position)
+{
+    if (ignoreShadows != 0) {
+        return 1.0;
+    }
+    vec3 direction = position - light_position;
+    vec3 abs_direction = abs(direction);
+    float z = max(abs_direction.x, max(abs_direction.y, abs_direction.z));
+    float n = shadowNearFar.x;
+    float f = shadowNearFar.y;
+    float ndc_depth = (f + n) / (f - n) - (2.0 * f * n) / ((f - n) * z);
+    float true_distance = (ndc_depth - 0.005) * 0.5 + 0.5;
+
+    // Sample around the direction in the plane perpendicular to it
+    vec3 tangent = normalize(cross(direction, abs_direction.y < 0.99 * z ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0)));
+    vec3 bitangent = normalize(cross(direction, tangent));
+    float offset = 0.001 * length(direction);
     vec2 poissonDisk[4] = vec2[](
                                  vec2( -0.94201624, -0.39906216 ),
                                  vec2( 0.94558609, -0.76890725 ),
                                  vec2( -0.094184101, -0.92938870 ),
                                  vec2( 0.34495938, 0.29387760 )
                                  );
+    float visibility = 0.0;
     for (int i=0;i<4;i++)
     {
-        visibility += is_visible(shadowMap, shadow_coord, poissonDisk[i] * 0.001f);
+        vec3 sample_direction = direction + offset * (poissonDisk[i].x * tangent + poissonDisk[i].y * bitangent);
+        visibility += is_visible_in_cube(shadowMap, sample_direction, true_distance);
     }
     return visibility * 0.25;
 }
@@ -0,0 +1,22 @@
+
+uniform sampler2D momentMap;
+uniform vec2 direction;
+uniform int radius;
+
+in vec2 uvs;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float sigma = max(0.5 * float(radius), 0.5);
+    vec4 moments = vec4(0.0);
+    float weight_sum = 0.0;
+    for (int i = -radius; i <= radius; i++)
+    {
+        float weight = exp(-float(i * i) / (2.0 * sigma * sigma));
+        moments += weight * texture(momentMap, uvs + float(i) * direction);
+        weight_sum += weight;
+    }
+    outColor = moments / weight_sum;
+}
@@ -0,0 +1,20 @@
+
+uniform vec2 exponents;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    float depth = gl_FragCoord.z;
+#ifdef EXPONENTIAL
+    depth = 2.0 * depth - 1.0;
+    float positive = exp(exponents.x * depth);
+    float negative = -exp(-exponents.y * depth);
+    outColor = vec4(positive, positive * positive, negative, negative * negative);
+#else
+    // Bias the second moment using the depth derivatives to reduce acne
+    float dx = dFdx(depth);
+    float dy = dFdy(depth);
+    outColor = vec4(depth, depth * depth + 0.25 * (dx * dx + dy * dy), 0.0, 0.0);
+#endif
+}
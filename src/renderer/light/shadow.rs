@@ -0,0 +1,521 @@
+use crate::core::*;
+use crate::renderer::light::*;
+use crate::renderer::*;
+
+///
+/// Settings that control how the shadow from a [DirectionalLight] or [SpotLight] is calculated.
+/// Adjust the biases to remove shadow acne (a surface incorrectly shadowing itself) without introducing
+/// peter-panning (the shadow becoming detached from the object casting it).
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// A constant bias that is subtracted from the depth of a surface before it is compared to the depth in the shadow map.
+    pub depth_bias: f32,
+    /// A bias that is scaled by the slope of the surface relative to the light direction and added to the depth bias.
+    /// This removes shadow acne on surfaces that are almost parallel to the light direction without increasing the bias on surfaces facing the light.
+    pub slope_bias: f32,
+    /// The distance in world space that a surface position is moved along the surface normal before it is looked up in the shadow map.
+    pub normal_offset: f32,
+    /// The [ShadowFilter] which is used to filter the shadow map.
+    pub filter: ShadowFilter,
+    /// The [DepthBias] (polygon offset) applied when rendering the geometries into the shadow map.
+    /// Unlike the other biases, which are applied when looking up in the shadow map, this is applied by the rasterizer when the shadow map is generated,
+    /// so the shadow map has to be generated again for a change to take effect.
+    pub polygon_offset: DepthBias,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            depth_bias: 0.005,
+            slope_bias: 0.0,
+            normal_offset: 0.0,
+            filter: ShadowFilter::Pcf { kernel_size: 3 },
+            polygon_offset: DepthBias::NONE,
+        }
+    }
+}
+
+///
+/// The filter used when looking up in a shadow map, see [ShadowSettings].
+///
+/// **Note:** The variance shadow map filters ([ShadowFilter::Vsm] and [ShadowFilter::Evsm]) require a moment texture which is created when the shadow map is generated,
+/// so the shadow map has to be generated again after changing to or between these filters.
+/// Until then, [ShadowFilter::Hard] is used.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShadowFilter {
+    /// No filtering, which results in hard and pixelated shadow edges.
+    Hard,
+    /// Percentage-closer filtering (PCF) which averages the shadow test over a square of texels in the shadow map.
+    Pcf {
+        /// The width and height of the square of texels, for example 3 gives a 3x3 kernel.
+        kernel_size: u32,
+    },
+    /// Percentage-closer soft shadows (PCSS) which gives shadows that are sharp close to the object casting the shadow and soft further away from it.
+    Pcss {
+        /// The size of the light source in texture coordinates of the shadow map. A larger light source gives softer shadows.
+        light_size: f32,
+        /// The width and height of the square of samples used both for the blocker search and for the filtering.
+        kernel_size: u32,
+    },
+    /// Variance shadow map (VSM) which stores the depth and the squared depth in a blurred moment texture.
+    Vsm {
+        /// The radius in texels of the gaussian blur applied to the moment texture.
+        blur_radius: u32,
+        /// A value in the range `[0..1]` which reduces light bleeding at the cost of darker shadow edges.
+        light_bleeding_reduction: f32,
+    },
+    /// Exponential variance shadow map (EVSM) which stores exponentially warped depth moments in a blurred moment texture.
+    /// This reduces the light bleeding compared to [ShadowFilter::Vsm].
+    Evsm {
+        /// The radius in texels of the gaussian blur applied to the moment texture.
+        blur_radius: u32,
+        /// A value in the range `[0..1]` which reduces light bleeding at the cost of darker shadow edges.
+        light_bleeding_reduction: f32,
+        /// The exponent used for the positive warp of the depth, must be smaller than about 42 to avoid overflow.
+        positive_exponent: f32,
+        /// The exponent used for the negative warp of the depth, must be smaller than about 42 to avoid overflow.
+        negative_exponent: f32,
+    },
+}
+
+impl ShadowFilter {
+    pub(super) fn id(&self) -> u8 {
+        match self {
+            Self::Hard => 0,
+            Self::Pcf { .. } => 1,
+            Self::Pcss { .. } => 2,
+            Self::Vsm { .. } => 3,
+            Self::Evsm { .. } => 4,
+        }
+    }
+
+    pub(super) fn kernel_size(&self) -> u32 {
+        match self {
+            Self::Hard => 1,
+            Self::Pcf { kernel_size } | Self::Pcss { kernel_size, .. } => {
+                (*kernel_size).clamp(1, 15)
+            }
+            Self::Vsm { .. } | Self::Evsm { .. } => 3,
+        }
+    }
+
+    ///
+    /// Returns the filter that is actually used when the moment texture was generated with the given filter, which differs from this filter
+    /// if this is a variance shadow map filter but the moment texture is missing or was generated with another filter.
+    ///
+    pub(super) fn effective(&self, moments_filter: Option<ShadowFilter>) -> ShadowFilter {
+        match self {
+            Self::Vsm { .. } | Self::Evsm { .. } => match moments_filter {
+                Some(filter) if filter.id() == self.id() => filter,
+                _ => Self::Hard,
+            },
+            filter => *filter,
+        }
+    }
+
+    ///
+    /// Returns the shader source which looks up the visibility of `shadow_coord` in the given sampler using this filter.
+    /// The layer is given if the sampler is a `sampler2DArray`.
+    ///
+    pub(super) fn lookup_source(&self, sampler: &str, layer: Option<&str>, i: u32) -> String {
+        let map = layer
+            .map(|layer| format!("{}, {}", sampler, layer))
+            .unwrap_or_else(|| sampler.to_string());
+        match self {
+            Self::Hard => format!("is_visible({}, shadow_coord.xy, shadow_coord.z)", map),
+            Self::Pcf { .. } => format!(
+                "calculate_shadow_pcf({}, shadow_coord, shadowKernelSize{}, 1.0 / vec2(textureSize({}, 0).xy))",
+                map, i, sampler
+            ),
+            Self::Pcss { .. } => format!(
+                "calculate_shadow_pcss({}, shadow_coord, shadowKernelSize{}, shadowFilter{}.x)",
+                map, i, i
+            ),
+            Self::Vsm { .. } => format!(
+                "calculate_shadow_vsm({}, shadow_coord, shadowFilter{}.x)",
+                map, i
+            ),
+            Self::Evsm { .. } => format!(
+                "calculate_shadow_evsm({}, shadow_coord, shadowFilter{}.x, shadowFilter{}.yz)",
+                map, i, i
+            ),
+        }
+    }
+
+    ///
+    /// Returns the filter parameters used by the shader source returned from [ShadowFilter::lookup_source].
+    ///
+    pub(super) fn parameters(&self) -> Vec4 {
+        match *self {
+            Self::Pcss { light_size, .. } => vec4(light_size, 0.0, 0.0, 0.0),
+            Self::Vsm {
+                light_bleeding_reduction,
+                ..
+            } => vec4(light_bleeding_reduction.clamp(0.0, 0.99), 0.0, 0.0, 0.0),
+            Self::Evsm {
+                light_bleeding_reduction,
+                positive_exponent,
+                negative_exponent,
+                ..
+            } => vec4(
+                light_bleeding_reduction.clamp(0.0, 0.99),
+                positive_exponent,
+                negative_exponent,
+                0.0,
+            ),
+            _ => vec4(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+}
+
+///
+/// A shadow map together with the data needed to look up in it.
+///
+pub(super) struct ShadowMap {
+    pub texture: DepthTexture2D,
+    matrix: Mat4,
+    moments: Option<(Texture2D, ShadowFilter)>,
+}
+
+impl ShadowMap {
+    ///
+    /// Renders the geometries which cast shadows and are visible from the shadow camera (see [Camera::cull_mask]) into a new shadow map.
+    /// If the filter is one of the variance shadow map filters, a blurred moment texture is rendered as well.
+    ///
+    pub fn new(
+        context: &Context,
+        shadow_camera: &Camera,
+        texture_size: u32,
+        settings: &ShadowSettings,
+        geometries: impl IntoIterator<Item = impl Geometry>,
+    ) -> Self {
+        let filter = settings.filter;
+        let mut texture = DepthTexture2D::new::<f32>(
+            context,
+            texture_size,
+            texture_size,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let geometries = geometries
+            .into_iter()
+            .filter(|g| g.cast_shadows() && is_visible(shadow_camera, g));
+        let moments = match filter {
+            ShadowFilter::Vsm { .. } | ShadowFilter::Evsm { .. } => {
+                let moment_texture = render_moment_texture(
+                    context,
+                    shadow_camera,
+                    &mut texture,
+                    settings,
+                    geometries,
+                );
+                Some((moment_texture, filter))
+            }
+            _ => {
+                let depth_material = DepthMaterial {
+                    render_states: RenderStates {
+                        write_mask: WriteMask::DEPTH,
+                        depth_bias: settings.polygon_offset,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                texture
+                    .as_depth_target()
+                    .clear(ClearState::default())
+                    .write(|| {
+                        for geometry in geometries {
+                            render_with_material(
+                                context,
+                                shadow_camera,
+                                &geometry,
+                                &depth_material,
+                                &[],
+                            );
+                        }
+                    });
+                None
+            }
+        };
+        Self {
+            texture,
+            matrix: shadow_matrix(shadow_camera),
+            moments,
+        }
+    }
+
+    ///
+    /// Returns the filter that is actually used, which differs from the requested filter
+    /// if a variance shadow map filter is requested but the moment texture was generated with another filter.
+    ///
+    fn filter(&self, settings: &ShadowSettings) -> ShadowFilter {
+        settings
+            .filter
+            .effective(self.moments.as_ref().map(|(_, filter)| *filter))
+    }
+
+    ///
+    /// Returns an id for the shader source returned from [ShadowMap::shader_source], in the range `[0..8)`.
+    ///
+    pub fn id(&self, settings: &ShadowSettings) -> u8 {
+        self.filter(settings).id()
+    }
+
+    ///
+    /// Returns the shader source containing the function `float calculate_shadow{i}(vec3 position, vec3 normal, vec3 light_direction)`.
+    ///
+    pub fn shader_source(&self, settings: &ShadowSettings, i: u32) -> String {
+        let lookup = self
+            .filter(settings)
+            .lookup_source(&format!("shadowMap{}", i), None, i);
+        format!(
+            "
+                uniform sampler2D shadowMap{};
+                uniform mat4 shadowMVP{};
+                uniform vec3 shadowBias{};
+                uniform int shadowKernelSize{};
+                uniform vec4 shadowFilter{};
+
+                float calculate_shadow{}(vec3 position, vec3 normal, vec3 light_direction)
+                {{
+                    if (ignoreShadows != 0) {{
+                        return 1.0;
+                    }}
+                    vec3 shadow_coord = shadow_coordinate(shadowMVP{}, shadowBias{}, position, normal, light_direction);
+                    return {};
+                }}
+            ",
+            i, i, i, i, i, i, i, i, lookup
+        )
+    }
+
+    ///
+    /// Sends the uniform data needed by [ShadowMap::shader_source] to the shader.
+    ///
+    pub fn use_uniforms(&self, program: &Program, settings: &ShadowSettings, i: u32) {
+        // Shadows are applied unless the material disables them afterwards, see Gm::receive_shadows
+        program.use_uniform_if_required("ignoreShadows", 0i32);
+        let filter = self.filter(settings);
+        match self.moments {
+            Some((ref moment_texture, _))
+                if matches!(filter, ShadowFilter::Vsm { .. } | ShadowFilter::Evsm { .. }) =>
+            {
+                program.use_texture(&format!("shadowMap{}", i), moment_texture)
+            }
+            _ => program.use_depth_texture(&format!("shadowMap{}", i), &self.texture),
+        }
+        program.use_uniform(&format!("shadowMVP{}", i), self.matrix);
+        program.use_uniform(
+            &format!("shadowBias{}", i),
+            vec3(
+                settings.depth_bias,
+                settings.slope_bias,
+                settings.normal_offset,
+            ),
+        );
+        program.use_uniform_if_required(
+            &format!("shadowKernelSize{}", i),
+            filter.kernel_size() as i32,
+        );
+        program.use_uniform_if_required(&format!("shadowFilter{}", i), filter.parameters());
+    }
+}
+
+///
+/// Renders the moments of the depth of the geometries as seen from the shadow camera into a new moment texture,
+/// which is blurred as specified by the variance shadow map filter in the settings.
+/// The depth texture is used as depth buffer and must have the same size as the moment texture.
+///
+pub(super) fn render_moment_texture(
+    context: &Context,
+    shadow_camera: &Camera,
+    depth_texture: &mut DepthTexture2D,
+    settings: &ShadowSettings,
+    geometries: impl IntoIterator<Item = impl Geometry>,
+) -> Texture2D {
+    let texture_size = depth_texture.width();
+    let filter = settings.filter;
+    let mut moment_texture = new_moment_texture(context, texture_size);
+    let moment_material = MomentMaterial {
+        filter,
+        depth_bias: settings.polygon_offset,
+    };
+    let clear_value = moment_material.moments(1.0);
+    RenderTarget::new(
+        moment_texture.as_color_target(None),
+        depth_texture.as_depth_target(),
+    )
+    .clear(ClearState::color_and_depth(
+        clear_value.x,
+        clear_value.y,
+        clear_value.z,
+        clear_value.w,
+        1.0,
+    ))
+    .write(|| {
+        for geometry in geometries {
+            render_with_material(context, shadow_camera, &geometry, &moment_material, &[]);
+        }
+    });
+    let blur_radius = match filter {
+        ShadowFilter::Vsm { blur_radius, .. } | ShadowFilter::Evsm { blur_radius, .. } => {
+            blur_radius
+        }
+        _ => 0,
+    };
+    if blur_radius > 0 {
+        let camera = Camera::new_2d(shadow_camera.viewport());
+        let texel_size = 1.0 / texture_size as f32;
+        let mut blurred_texture = new_moment_texture(context, texture_size);
+        blurred_texture.as_color_target(None).write(|| {
+            apply_screen_material(
+                context,
+                &MomentBlurMaterial {
+                    moment_texture: &moment_texture,
+                    direction: vec2(texel_size, 0.0),
+                    radius: blur_radius,
+                },
+                &camera,
+                &[],
+            );
+        });
+        moment_texture.as_color_target(None).write(|| {
+            apply_screen_material(
+                context,
+                &MomentBlurMaterial {
+                    moment_texture: &blurred_texture,
+                    direction: vec2(0.0, texel_size),
+                    radius: blur_radius,
+                },
+                &camera,
+                &[],
+            );
+        });
+    }
+    moment_texture
+}
+
+fn new_moment_texture(context: &Context, texture_size: u32) -> Texture2D {
+    Texture2D::new_empty::<[f32; 4]>(
+        context,
+        texture_size,
+        texture_size,
+        Interpolation::Linear,
+        Interpolation::Linear,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    )
+}
+
+struct MomentMaterial {
+    filter: ShadowFilter,
+    depth_bias: DepthBias,
+}
+
+impl MomentMaterial {
+    fn exponents(&self) -> Vec2 {
+        match self.filter {
+            ShadowFilter::Evsm {
+                positive_exponent,
+                negative_exponent,
+                ..
+            } => vec2(positive_exponent, negative_exponent),
+            _ => vec2(0.0, 0.0),
+        }
+    }
+
+    fn moments(&self, depth: f32) -> Vec4 {
+        if let ShadowFilter::Evsm { .. } = self.filter {
+            let exponents = self.exponents();
+            let depth = 2.0 * depth - 1.0;
+            let positive = (exponents.x * depth).exp();
+            let negative = -(-exponents.y * depth).exp();
+            vec4(positive, positive * positive, negative, negative * negative)
+        } else {
+            vec4(depth, depth * depth, 0.0, 0.0)
+        }
+    }
+}
+
+impl Material for MomentMaterial {
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        if let ShadowFilter::Evsm { .. } = self.filter {
+            format!(
+                "#define EXPONENTIAL\n{}",
+                include_str!("shaders/shadow_moments.frag")
+            )
+        } else {
+            include_str!("shaders/shadow_moments.frag").to_string()
+        }
+    }
+
+    fn id(&self) -> u16 {
+        if let ShadowFilter::Evsm { .. } = self.filter {
+            0b1u16 << 15 | 0b1001u16
+        } else {
+            0b1u16 << 15 | 0b1000u16
+        }
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes::NONE
+    }
+
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+        program.use_uniform_if_required("exponents", self.exponents());
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_bias: self.depth_bias,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
+
+struct MomentBlurMaterial<'a> {
+    moment_texture: &'a Texture2D,
+    direction: Vec2,
+    radius: u32,
+}
+
+impl Material for MomentBlurMaterial<'_> {
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        include_str!("shaders/shadow_blur.frag").to_string()
+    }
+
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1010u16
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            uv: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _lights: &[&dyn Light]) {
+        program.use_texture("momentMap", self.moment_texture);
+        program.use_uniform("direction", self.direction);
+        program.use_uniform("radius", self.radius as i32);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            depth_test: DepthTest::Always,
+            ..Default::default()
+        }
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
@@ -8,8 +8,7 @@ use crate::renderer::*;
 ///
 pub struct SpotLight {
     context: Context,
-    shadow_texture: Option<DepthTexture2D>,
-    shadow_matrix: Mat4,
+    shadow: Option<ShadowMap>,
     /// The intensity of the light. This allows for higher intensity than 1 which can be used to simulate high intensity light sources like the sun.
     pub intensity: f32,
     /// The base color of the light.
@@ -22,6 +21,11 @@ pub struct SpotLight {
     pub cutoff: Radians,
     /// The [Attenuation] of the light.
     pub attenuation: Attenuation,
+    /// The [ShadowSettings] used when calculating the shadow from this light.
+    pub shadow_settings: ShadowSettings,
+    /// A bitmask of the layers which cast shadows from this light. A geometry is only rendered into the shadow map if at least one of its layers (see [Geometry::layers]) is in this mask.
+    /// The default value contains all layers.
+    pub shadow_cull_mask: u32,
 }
 
 impl SpotLight {
@@ -37,14 +41,15 @@ impl SpotLight {
     ) -> SpotLight {
         SpotLight {
             context: context.clone(),
-            shadow_texture: None,
+            shadow: None,
             intensity,
             color,
             position: *position,
             direction: *direction,
             cutoff: cutoff.into(),
             attenuation,
-            shadow_matrix: Mat4::identity(),
+            shadow_settings: ShadowSettings::default(),
+            shadow_cull_mask: u32::MAX,
         }
     }
 
@@ -53,12 +58,12 @@ impl SpotLight {
     /// Only necessary if you want to disable the shadow, if you want to update the shadow, just use [SpotLight::generate_shadow_map].
     ///
     pub fn clear_shadow_map(&mut self) {
-        self.shadow_texture = None;
-        self.shadow_matrix = Mat4::identity();
+        self.shadow = None;
     }
 
     ///
     /// Generate a shadow map which is used to simulate shadows from the spot light onto the geometries given as input.
+    /// Geometries which do not cast shadows (see [Geometry::cast_shadows]) or are not in the [SpotLight::shadow_cull_mask] are not rendered into the shadow map.
     /// It is recomended that the texture size is power of 2.
     /// If the shadows are too low resolution (the edges between shadow and non-shadow are pixelated) try to increase the texture size.
     ///
@@ -83,7 +88,7 @@ impl SpotLight {
             }
         }
 
-        let shadow_camera = Camera::new_perspective(
+        let mut shadow_camera = Camera::new_perspective(
             viewport,
             position,
             position + direction,
@@ -92,58 +97,30 @@ impl SpotLight {
             z_near.max(0.01),
             z_far,
         );
-        self.shadow_matrix = shadow_matrix(&shadow_camera);
-
-        let mut shadow_texture = DepthTexture2D::new::<f32>(
+        shadow_camera.cull_mask = self.shadow_cull_mask;
+        self.shadow = Some(ShadowMap::new(
             &self.context,
+            &shadow_camera,
             texture_size,
-            texture_size,
-            Wrapping::ClampToEdge,
-            Wrapping::ClampToEdge,
-        );
-        let depth_material = DepthMaterial {
-            render_states: RenderStates {
-                write_mask: WriteMask::DEPTH,
-                ..Default::default()
-            },
-            ..Default::default()
-        };
-        shadow_texture
-            .as_depth_target()
-            .clear(ClearState::default())
-            .write(|| {
-                for geometry in geometries
-                    .into_iter()
-                    .filter(|g| shadow_camera.in_frustum(&g.aabb()))
-                {
-                    render_with_material(
-                        &self.context,
-                        &shadow_camera,
-                        &geometry,
-                        &depth_material,
-                        &[],
-                    );
-                }
-            });
-        self.shadow_texture = Some(shadow_texture);
+            &self.shadow_settings,
+            geometries,
+        ));
     }
 
     ///
     /// Returns a reference to the shadow map if it has been generated.
     ///
     pub fn shadow_map(&self) -> Option<&DepthTexture2D> {
-        self.shadow_texture.as_ref()
+        self.shadow.as_ref().map(|shadow| &shadow.texture)
     }
 }
 
 impl Light for SpotLight {
     fn shader_source(&self, i: u32) -> String {
-        if self.shadow_texture.is_some() {
+        if let Some(ref shadow) = self.shadow {
             format!(
                 "
-                    uniform sampler2D shadowMap{};
-                    uniform mat4 shadowMVP{};
-        
+                    {}
                     uniform vec3 color{};
                     uniform vec3 attenuation{};
                     uniform vec3 position{};
@@ -163,12 +140,12 @@ impl Light for SpotLight {
                             vec3 light_color = attenuate(color{}, attenuation{}, distance);
                             result = calculate_light(light_color, light_direction, surface_color, view_direction, normal, 
                                 metallic, roughness) * (1.0 - smoothstep(0.75 * cutoff, cutoff, angle));
-                            result *= calculate_shadow(shadowMap{}, shadowMVP{}, position);
+                            result *= calculate_shadow{}(position, normal, light_direction);
                         }}
                         return result;
                     }}
                 
-                ", i, i, i, i, i, i, i, i, i, i, i, i, i, i, i)
+                ", shadow.shader_source(&self.shadow_settings, i), i, i, i, i, i, i, i, i, i, i, i, i)
         } else {
             format!(
                 "
@@ -199,9 +176,8 @@ impl Light for SpotLight {
         }
     }
     fn use_uniforms(&self, program: &Program, i: u32) {
-        if let Some(ref tex) = self.shadow_texture {
-            program.use_depth_texture(&format!("shadowMap{}", i), tex);
-            program.use_uniform(&format!("shadowMVP{}", i), self.shadow_matrix);
+        if let Some(ref shadow) = self.shadow {
+            shadow.use_uniforms(program, &self.shadow_settings, i);
         }
         program.use_uniform(
             &format!("color{}", i),
@@ -221,8 +197,8 @@ impl Light for SpotLight {
     }
 
     fn id(&self) -> u8 {
-        if self.shadow_texture.is_some() {
-            0b1u8 << 7 | 0b101u8
+        if let Some(ref shadow) = self.shadow {
+            0b1u8 << 7 | shadow.id(&self.shadow_settings) << 4 | 0b101u8
         } else {
             0b1u8 << 7 | 0b110u8
         }
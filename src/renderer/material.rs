@@ -25,6 +25,9 @@ macro_rules! impl_material_body {
         fn id(&self) -> u16 {
             self.$inner().id()
         }
+        fn texture_key(&self) -> u64 {
+            self.$inner().texture_key()
+        }
     };
 }
 
@@ -74,6 +77,10 @@ mod isosurface_material;
 #[doc(inline)]
 pub use isosurface_material::*;
 
+mod velocity_material;
+#[doc(inline)]
+pub use velocity_material::*;
+
 use std::{ops::Deref, sync::Arc};
 
 ///
@@ -195,6 +202,7 @@ pub trait Material {
     ///
     /// **Note:** The last bit is reserved to internally implemented materials, so if implementing the [Material] trait
     /// outside of this crate, always return an id that is smaller than `0b1u16 << 15`.
+    /// Transparent materials with an id of `0b1u16 << 14` or larger are not rendered using order independent transparency (see [RenderTarget::set_order_independent_transparency]).
     ///
     fn id(&self) -> u16;
 
@@ -218,6 +226,29 @@ pub trait Material {
     /// Returns the type of material.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns a key which is the same for materials using the same textures, so that objects using the same textures can be rendered after each other (see [RenderQueue]).
+    /// The default implementation returns zero, ie. the textures are not taken into account.
+    ///
+    fn texture_key(&self) -> u64 {
+        0
+    }
+}
+
+///
+/// Returns a key identifying the given textures which can be used to implement [Material::texture_key].
+///
+pub(crate) fn texture_key<'a>(textures: impl IntoIterator<Item = &'a Option<Texture2DRef>>) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for texture in textures {
+        texture
+            .as_ref()
+            .map(|t| Arc::as_ptr(&t.texture))
+            .hash(&mut hasher);
+    }
+    hasher.finish()
 }
 
 ///
@@ -283,6 +314,9 @@ impl<T: Material> Material for std::sync::RwLock<T> {
     fn id(&self) -> u16 {
         self.read().unwrap().id()
     }
+    fn texture_key(&self) -> u64 {
+        self.read().unwrap().texture_key()
+    }
 }
 
 fn is_transparent(cpu_material: &CpuMaterial) -> bool {
@@ -98,7 +98,7 @@ impl Material for ColorMaterial {
         if self.texture.is_some() {
             shader.push_str("#define USE_TEXTURE\nin vec2 uvs;\n");
         }
-        shader.push_str(include_str!("../../core/shared.frag"));
+        shader.push_str("#include \"shared.frag\"\n");
         shader.push_str(ColorSpace::fragment_shader_source());
         shader.push_str(include_str!("shaders/color_material.frag"));
         shader
@@ -130,4 +130,8 @@ impl Material for ColorMaterial {
             MaterialType::Opaque
         }
     }
+
+    fn texture_key(&self) -> u64 {
+        texture_key([&self.texture])
+    }
 }
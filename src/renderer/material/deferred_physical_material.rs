@@ -188,7 +188,7 @@ impl Material for DeferredPhysicalMaterial {
     }
 
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
-        let mut output = include_str!("../../core/shared.frag").to_string();
+        let mut output = "#include \"shared.frag\"\n".to_string();
         if self.albedo_texture.is_some()
             || self.metallic_roughness_texture.is_some()
             || self.normal_texture.is_some()
@@ -279,6 +279,16 @@ impl Material for DeferredPhysicalMaterial {
     fn material_type(&self) -> MaterialType {
         MaterialType::Deferred
     }
+
+    fn texture_key(&self) -> u64 {
+        texture_key([
+            &self.albedo_texture,
+            &self.metallic_roughness_texture,
+            &self.occlusion_texture,
+            &self.normal_texture,
+            &self.emissive_texture,
+        ])
+    }
 }
 
 impl Default for DeferredPhysicalMaterial {
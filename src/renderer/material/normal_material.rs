@@ -96,6 +96,10 @@ impl Material for NormalMaterial {
     fn material_type(&self) -> MaterialType {
         MaterialType::Opaque
     }
+
+    fn texture_key(&self) -> u64 {
+        texture_key([&self.normal_texture])
+    }
 }
 
 impl Default for NormalMaterial {
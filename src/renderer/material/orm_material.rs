@@ -131,6 +131,10 @@ impl Material for ORMMaterial {
     fn material_type(&self) -> MaterialType {
         MaterialType::Opaque
     }
+
+    fn texture_key(&self) -> u64 {
+        texture_key([&self.metallic_roughness_texture, &self.occlusion_texture])
+    }
 }
 
 impl Default for ORMMaterial {
@@ -258,6 +258,16 @@ impl Material for PhysicalMaterial {
             MaterialType::Opaque
         }
     }
+
+    fn texture_key(&self) -> u64 {
+        texture_key([
+            &self.albedo_texture,
+            &self.metallic_roughness_texture,
+            &self.occlusion_texture,
+            &self.normal_texture,
+            &self.emissive_texture,
+        ])
+    }
 }
 
 impl Default for PhysicalMaterial {
@@ -0,0 +1,15 @@
+uniform mat4 currentViewProjection;
+uniform mat4 previousViewProjection;
+
+in vec3 pos;
+in vec3 previous_pos;
+
+layout (location = 0) out vec4 outColor;
+
+void main()
+{
+    vec4 current_position = currentViewProjection * vec4(pos, 1.0);
+    vec4 previous_position = previousViewProjection * vec4(previous_pos, 1.0);
+    vec2 velocity = 0.5 * (current_position.xy / current_position.w - previous_position.xy / previous_position.w);
+    outColor = vec4(velocity, 0.0, 1.0);
+}
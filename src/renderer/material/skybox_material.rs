@@ -14,7 +14,7 @@ impl Material for SkyboxMaterial {
     fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
         format!(
             "{}{}{}{}",
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             ToneMapping::fragment_shader_source(),
             ColorSpace::fragment_shader_source(),
             include_str!("shaders/skybox_material.frag")
@@ -0,0 +1,67 @@
+use crate::core::*;
+use crate::renderer::*;
+
+///
+/// Render the object with colors that reflect its screen space velocity, ie. how far it has moved on the screen since the previous frame.
+/// The movement in UV coordinates (0 to 1 across the screen) is written to the red and green channels, so the object should be rendered into a floating point texture.
+///
+/// The movement of the camera is found using [VelocityMaterial::previous_view_projection] and the movement of a [Mesh] or [InstancedMesh]
+/// is found using the previous transformation of the mesh (see [Mesh::set_previous_transformation]) and the previous transformations of the instances (see [InstancedMesh::set_instances]).
+/// Use the [VelocityBuffer] to keep track of the previous camera.
+///
+/// This material requires that the vertex shader of the geometry outputs the position in world space in the previous frame `out vec3 previous_pos;`,
+/// which is the case for all geometries in this crate.
+///
+#[derive(Clone)]
+pub struct VelocityMaterial {
+    /// The view projection matrix of the camera in the previous frame, without any [Camera::jitter].
+    pub previous_view_projection: Mat4,
+    /// Render states.
+    pub render_states: RenderStates,
+}
+
+impl Default for VelocityMaterial {
+    fn default() -> Self {
+        Self {
+            previous_view_projection: Mat4::identity(),
+            render_states: RenderStates::default(),
+        }
+    }
+}
+
+impl FromCpuMaterial for VelocityMaterial {
+    fn from_cpu_material(_context: &Context, _cpu_material: &CpuMaterial) -> Self {
+        Self::default()
+    }
+}
+
+impl Material for VelocityMaterial {
+    fn id(&self) -> u16 {
+        0b1u16 << 15 | 0b1011u16
+    }
+
+    fn fragment_shader_source(&self, _lights: &[&dyn Light]) -> String {
+        include_str!("shaders/velocity_material.frag").to_string()
+    }
+
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        FragmentAttributes {
+            position: true,
+            ..FragmentAttributes::NONE
+        }
+    }
+
+    fn use_uniforms(&self, program: &Program, camera: &Camera, _lights: &[&dyn Light]) {
+        // The velocity should not include the jitter
+        program.use_uniform("currentViewProjection", camera.projection() * camera.view());
+        program.use_uniform("previousViewProjection", self.previous_view_projection);
+    }
+
+    fn render_states(&self) -> RenderStates {
+        self.render_states
+    }
+
+    fn material_type(&self) -> MaterialType {
+        MaterialType::Opaque
+    }
+}
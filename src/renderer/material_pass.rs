@@ -0,0 +1,143 @@
+use crate::renderer::*;
+
+///
+/// Wraps a transparent material so that it accumulates the weighted and premultiplied color, used for weighted blended order independent transparency.
+/// The fragment shader of the material is wrapped so that the output of the material can be modified.
+/// This requires that the material writes its output to `outColor`, which is the case for all materials in this crate.
+///
+pub(crate) struct OitAccumulationMaterial<'a>(pub &'a dyn Material);
+
+impl OitAccumulationMaterial<'_> {
+    ///
+    /// Returns whether a material with the given id can be wrapped.
+    /// The id of the wrapper is created from the id of the material and the ids of custom materials are only unique if they are smaller than `0b1u16 << 14`.
+    ///
+    pub fn is_supported(material_id: u16) -> bool {
+        material_id & 0b1u16 << 15 != 0 || material_id < 0b1u16 << 14
+    }
+}
+
+impl Material for OitAccumulationMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        format!(
+            "#define main material_main\n{}\n#undef main\nvoid main()\n{{\n    material_main();\n    {}\n}}\n",
+            self.0.fragment_shader_source(lights),
+            "
+            float weight = clamp(pow(min(1.0, outColor.a * 10.0) + 0.01, 3.0) * 1e8 * pow(1.0 - gl_FragCoord.z * 0.9, 3.0), 1e-2, 3e3);
+            outColor = vec4(outColor.rgb * outColor.a, outColor.a) * weight;
+            "
+        )
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            blend: Blend::ADD,
+            ..self.0.render_states()
+        }
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        // The internal material ids only use the lowest eight bits besides the last bit
+        let id = self.0.id();
+        if id & 0b1u16 << 15 == 0 {
+            0b1u16 << 15 | 0b1u16 << 14 | id
+        } else {
+            id | 0b1u16 << 13
+        }
+    }
+    fn texture_key(&self) -> u64 {
+        self.0.texture_key()
+    }
+}
+
+///
+/// Wraps a transparent material so that it multiplies the revealage, ie. the product of one minus the alpha of all transparent objects, used for weighted blended order independent transparency.
+/// Only the render states are changed, so the shader program of the material is used.
+///
+pub(crate) struct OitRevealageMaterial<'a>(pub &'a dyn Material);
+
+impl Material for OitRevealageMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        RenderStates {
+            write_mask: WriteMask::COLOR,
+            blend: Blend::Enabled {
+                source_rgb_multiplier: BlendMultiplierType::Zero,
+                source_alpha_multiplier: BlendMultiplierType::Zero,
+                destination_rgb_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+                destination_alpha_multiplier: BlendMultiplierType::OneMinusSrcAlpha,
+                rgb_equation: BlendEquationType::Add,
+                alpha_equation: BlendEquationType::Add,
+            },
+            ..self.0.render_states()
+        }
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+    fn texture_key(&self) -> u64 {
+        self.0.texture_key()
+    }
+}
+
+///
+/// Wraps a material so that it shades an object after its depth has been rendered in a depth pre-pass,
+/// ie. only the closest surface is shaded and the depth is not written again.
+/// Only the render states are changed, so the shader program of the material is used.
+///
+pub(crate) struct DepthPrePassShadingMaterial<'a>(pub &'a dyn Material);
+
+impl Material for DepthPrePassShadingMaterial<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights)
+    }
+    fn render_states(&self) -> RenderStates {
+        let render_states = self.0.render_states();
+        RenderStates {
+            write_mask: WriteMask {
+                depth: false,
+                ..render_states.write_mask
+            },
+            depth_test: if render_states.depth_test == DepthTest::Less {
+                DepthTest::LessOrEqual
+            } else {
+                render_states.depth_test
+            },
+            ..render_states
+        }
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+    fn texture_key(&self) -> u64 {
+        self.0.texture_key()
+    }
+}
@@ -15,6 +15,14 @@ macro_rules! impl_object_body {
         fn material_type(&self) -> MaterialType {
             self.$inner().material_type()
         }
+
+        fn state_key(&self, lights: &[&dyn Light]) -> Option<StateKey> {
+            self.$inner().state_key(lights)
+        }
+
+        fn use_material(&self, callback: &mut dyn FnMut(&dyn Material)) {
+            self.$inner().use_material(callback)
+        }
     };
 }
 
@@ -72,6 +80,23 @@ pub trait Object: Geometry {
     /// Returns the type of material applied to this object.
     ///
     fn material_type(&self) -> MaterialType;
+
+    ///
+    /// Returns a key identifying the shader program and textures used when rendering this object with the given lights, see [StateKey].
+    /// It is used by [RenderQueue] to render objects using the same shader program and textures after each other.
+    /// The default implementation returns `None`, in which case the object is only sorted by distance to the camera.
+    ///
+    fn state_key(&self, _lights: &[&dyn Light]) -> Option<StateKey> {
+        None
+    }
+
+    ///
+    /// Calls the callback with the material used when rendering this object with [Object::render], if the object is rendered with a single [Material].
+    /// This makes it possible to render the object with a material derived from its own material using [Geometry::render_with_material],
+    /// which is used for example in a depth pre-pass (see [RenderTarget::set_depth_pre_pass]).
+    /// The default implementation does not call the callback.
+    ///
+    fn use_material(&self, _callback: &mut dyn FnMut(&dyn Material)) {}
 }
 
 use std::ops::Deref;
@@ -107,4 +132,12 @@ impl<T: Object> Object for std::sync::RwLock<T> {
     fn material_type(&self) -> MaterialType {
         self.read().unwrap().material_type()
     }
+
+    fn state_key(&self, lights: &[&dyn Light]) -> Option<StateKey> {
+        self.read().unwrap().state_key(lights)
+    }
+
+    fn use_material(&self, callback: &mut dyn FnMut(&dyn Material)) {
+        self.read().unwrap().use_material(callback)
+    }
 }
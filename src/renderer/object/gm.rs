@@ -10,6 +10,13 @@ pub struct Gm<G: Geometry, M: Material> {
     pub geometry: G,
     /// The material applied to the geometry
     pub material: M,
+    /// A bitmask of the layers this object belongs to, see [Geometry::layers]. The default value is `1`, ie. the first layer.
+    pub layers: u32,
+    /// Whether or not this object is rendered into shadow maps, see [Geometry::cast_shadows]. The default value is `true`.
+    pub cast_shadows: bool,
+    /// Whether or not shadows are applied to this object when it is rendered. The default value is `true`.
+    /// This only affects materials which calculate the lighting when the object is rendered, ie. it does not affect deferred materials like [DeferredPhysicalMaterial].
+    pub receive_shadows: bool,
 }
 
 impl<G: Geometry, M: Material> Gm<G, M> {
@@ -17,7 +24,13 @@ impl<G: Geometry, M: Material> Gm<G, M> {
     /// Creates a new [Gm] from a geometry and material.
     ///
     pub fn new(geometry: G, material: M) -> Self {
-        Self { geometry, material }
+        Self {
+            geometry,
+            material,
+            layers: 1,
+            cast_shadows: true,
+            receive_shadows: true,
+        }
     }
 }
 
@@ -35,6 +48,9 @@ impl<G: Geometry + Clone, M: Material + Clone> Clone for Gm<G, M> {
         Self {
             geometry: self.geometry.clone(),
             material: self.material.clone(),
+            layers: self.layers,
+            cast_shadows: self.cast_shadows,
+            receive_shadows: self.receive_shadows,
         }
     }
 }
@@ -54,7 +70,15 @@ impl<G: Geometry, M: Material> std::ops::DerefMut for Gm<G, M> {
 }
 
 impl<G: Geometry, M: Material> Geometry for Gm<G, M> {
-    impl_geometry_body!(deref);
+    impl_geometry_body!(deref, without_layers);
+
+    fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    fn cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
 
     fn animate(&mut self, time: f32) {
         self.geometry.animate(time)
@@ -63,10 +87,53 @@ impl<G: Geometry, M: Material> Geometry for Gm<G, M> {
 
 impl<G: Geometry, M: Material> Object for Gm<G, M> {
     fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
-        self.render_with_material(&self.material, camera, lights)
+        self.use_material(&mut |material| self.render_with_material(material, camera, lights))
     }
 
     fn material_type(&self) -> MaterialType {
         self.material.material_type()
     }
+
+    fn state_key(&self, lights: &[&dyn Light]) -> Option<StateKey> {
+        Some(StateKey::new(&self.geometry, &self.material, lights))
+    }
+
+    fn use_material(&self, callback: &mut dyn FnMut(&dyn Material)) {
+        if self.receive_shadows {
+            callback(&self.material)
+        } else {
+            callback(&IgnoreShadows(&self.material))
+        }
+    }
+}
+
+///
+/// Renders with the given material, except that shadows are not applied, see [Gm::receive_shadows].
+/// The shader program is the same as for the given material, shadows are instead disabled using a uniform.
+///
+struct IgnoreShadows<'a>(&'a dyn Material);
+
+impl Material for IgnoreShadows<'_> {
+    fn fragment_shader_source(&self, lights: &[&dyn Light]) -> String {
+        self.0.fragment_shader_source(lights)
+    }
+    fn fragment_attributes(&self) -> FragmentAttributes {
+        self.0.fragment_attributes()
+    }
+    fn use_uniforms(&self, program: &Program, camera: &Camera, lights: &[&dyn Light]) {
+        self.0.use_uniforms(program, camera, lights);
+        program.use_uniform_if_required("ignoreShadows", 1i32);
+    }
+    fn render_states(&self) -> RenderStates {
+        self.0.render_states()
+    }
+    fn material_type(&self) -> MaterialType {
+        self.0.material_type()
+    }
+    fn id(&self) -> u16 {
+        self.0.id()
+    }
+    fn texture_key(&self) -> u64 {
+        self.0.texture_key()
+    }
 }
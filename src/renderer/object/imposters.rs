@@ -117,6 +117,10 @@ impl Object for Imposters {
     fn material_type(&self) -> MaterialType {
         self.material.material_type()
     }
+
+    fn state_key(&self, lights: &[&dyn Light]) -> Option<StateKey> {
+        Some(StateKey::new(self, &self.material, lights))
+    }
 }
 
 struct ImpostersMaterial {
@@ -222,7 +226,7 @@ impl Material for ImpostersMaterial {
             "{}{}{}{}",
             ToneMapping::fragment_shader_source(),
             ColorSpace::fragment_shader_source(),
-            include_str!("../../core/shared.frag"),
+            "#include \"shared.frag\"\n",
             include_str!("shaders/imposter.frag")
         )
     }
@@ -117,10 +117,7 @@ impl<M: Material + FromCpuMaterial + Clone + Default> InstancedModel<M> {
                 } else {
                     M::default()
                 };
-                let mut gm = Gm {
-                    geometry: InstancedMesh::new(context, instances, geometry),
-                    material,
-                };
+                let mut gm = Gm::new(InstancedMesh::new(context, instances, geometry), material);
                 gm.set_transformation(primitive.transformation);
                 gms.push(InstancedModelPart {
                     gm,
@@ -114,10 +114,7 @@ impl<M: Material + FromCpuMaterial + Clone + Default> Model<M> {
                 } else {
                     M::default()
                 };
-                let mut gm = Gm {
-                    geometry: Mesh::new(context, geometry),
-                    material,
-                };
+                let mut gm = Gm::new(Mesh::new(context, geometry), material);
                 gm.set_transformation(primitive.transformation);
                 gms.push(ModelPart {
                     gm,
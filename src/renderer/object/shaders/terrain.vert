@@ -3,6 +3,7 @@ uniform mat4 viewProjectionMatrix;
 in vec3 position;
 
 out vec3 pos;
+out vec3 previous_pos;
 out vec2 uvs;
 out vec4 col;
 
@@ -19,6 +20,7 @@ void main()
 {
     vec4 worldPos = vec4(position, 1.);
     pos = worldPos.xyz;
+    previous_pos = pos;
     uvs = worldPos.xz;
     col = vec4(1.0);
 #ifdef USE_NORMALS
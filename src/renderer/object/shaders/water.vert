@@ -13,6 +13,7 @@ in vec3 position;
 out vec2 uvs;
 out vec3 nor;
 out vec3 pos;
+out vec3 previous_pos;
 out vec4 col;
 
 void main()
@@ -53,5 +54,6 @@ void main()
     
     gl_Position = viewProjection * vec4(pos, 1.);
     uvs = pos.xz;
+    previous_pos = pos;
     col = vec4(1.0);
 }
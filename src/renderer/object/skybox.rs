@@ -149,9 +149,14 @@ impl Geometry for Skybox {
         _attributes: FragmentAttributes,
     ) {
         program.use_uniform("view", camera.view());
-        program.use_uniform("projection", camera.projection());
+        program.use_uniform("projection", camera.jittered_projection());
         program.use_vertex_attribute("position", &self.vertex_buffer);
-        program.draw_arrays(render_states, camera.viewport(), 36);
+        program.draw_arrays(
+            render_states,
+            camera.viewport(),
+            PrimitiveType::Triangles,
+            36,
+        );
     }
 
     fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
@@ -203,4 +208,8 @@ impl Object for Skybox {
     fn material_type(&self) -> MaterialType {
         MaterialType::Opaque
     }
+
+    fn state_key(&self, lights: &[&dyn Light]) -> Option<StateKey> {
+        Some(StateKey::new(self, &self.material, lights))
+    }
 }
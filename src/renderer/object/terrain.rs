@@ -355,12 +355,20 @@ impl Geometry for TerrainPatch {
         render_states: RenderStates,
         attributes: FragmentAttributes,
     ) {
-        program.use_uniform("viewProjectionMatrix", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjectionMatrix",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_vertex_attribute("position", &self.positions_buffer);
         if attributes.normal || attributes.tangents {
             program.use_vertex_attribute("normal", &self.normals_buffer);
         }
-        program.draw_elements(render_states, camera.viewport(), &self.index_buffer);
+        program.draw_elements(
+            render_states,
+            camera.viewport(),
+            PrimitiveType::Triangles,
+            &self.index_buffer,
+        );
     }
 
     fn id(&self, required_attributes: FragmentAttributes) -> u16 {
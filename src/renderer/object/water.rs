@@ -226,7 +226,10 @@ impl Geometry for WaterPatch {
             "offset",
             self.center + vec3(self.offset.x, 0.0, self.offset.y),
         );
-        program.use_uniform("viewProjection", camera.projection() * camera.view());
+        program.use_uniform(
+            "viewProjection",
+            camera.jittered_projection() * camera.view(),
+        );
         program.use_uniform("time", self.time * 0.001);
         program.use_uniform_array(
             "waveParameters",
@@ -246,7 +249,12 @@ impl Geometry for WaterPatch {
         );
 
         program.use_vertex_attribute("position", &self.position_buffer);
-        program.draw_elements(render_states, camera.viewport(), &self.index_buffer);
+        program.draw_elements(
+            render_states,
+            camera.viewport(),
+            PrimitiveType::Triangles,
+            &self.index_buffer,
+        );
     }
 
     fn vertex_shader_source(&self, _required_attributes: FragmentAttributes) -> String {
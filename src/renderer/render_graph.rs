@@ -0,0 +1,528 @@
+use crate::renderer::*;
+
+///
+/// A handle to a resource in a [RenderGraph], ie. a transient texture created by the graph or an imported resource.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GraphResource(usize);
+
+///
+/// The format of a color texture created by a [RenderGraph].
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphColorFormat {
+    /// Four 8 bit channels (red, green, blue and alpha).
+    Rgba8,
+    /// Four 16 bit floating point channels (red, green, blue and alpha).
+    Rgba16F,
+    /// Four 32 bit floating point channels (red, green, blue and alpha).
+    Rgba32F,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum ResourceKind {
+    Color {
+        width: u32,
+        height: u32,
+        format: GraphColorFormat,
+    },
+    Depth {
+        width: u32,
+        height: u32,
+    },
+    Imported,
+}
+
+struct Resource {
+    name: String,
+    kind: ResourceKind,
+}
+
+type PassCallback<'a> = Box<dyn FnOnce(&PassInputs<'_>, &mut PassOutputs<'_>) + 'a>;
+
+struct Pass<'a> {
+    name: String,
+    reads: Vec<GraphResource>,
+    writes: Vec<GraphResource>,
+    execute: PassCallback<'a>,
+}
+
+enum Slot {
+    Empty,
+    Color(TransientTexture<Texture2D>),
+    Depth(TransientTexture<DepthTexture2D>),
+}
+
+///
+/// A declarative description of the rendering of a frame as a list of passes,
+/// where each pass declares which resources (textures) it reads and writes.
+/// Typical passes are a depth pre-pass, shadow map generation, a geometry pass, a lighting pass, a forward pass for transparent objects and a chain of post effects.
+///
+/// When the graph is executed (see [RenderGraph::execute]), the passes are executed in the order they were added,
+/// except the passes whose output is never used, which are skipped.
+/// The output of the graph is the imported resources (see [RenderGraph::import]), including the screen (see [RenderGraph::screen]),
+/// so a pass is only executed if it writes an imported resource or a texture which is used by another executed pass.
+///
+/// The textures created by the graph (see [RenderGraph::create_color_texture] and [RenderGraph::create_depth_texture]) are transient.
+/// They are borrowed from the texture pool in the [Context] right before the first pass using them
+/// and returned right after the last pass using them, so textures with the same size and format are reused (aliased) between passes and between frames.
+/// The content of a texture is undefined before the first pass writing to it, so that pass should clear it.
+///
+pub struct RenderGraph<'a> {
+    context: Context,
+    resources: Vec<Resource>,
+    passes: Vec<Pass<'a>>,
+    screen: GraphResource,
+}
+
+impl<'a> RenderGraph<'a> {
+    ///
+    /// Creates a new empty render graph.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            resources: vec![Resource {
+                name: "screen".to_string(),
+                kind: ResourceKind::Imported,
+            }],
+            passes: Vec::new(),
+            screen: GraphResource(0),
+        }
+    }
+
+    ///
+    /// Returns the imported resource which represents the render target given to [RenderGraph::execute], typically the screen.
+    /// A pass that writes this resource can render into the render target using [PassOutputs::screen].
+    ///
+    pub fn screen(&self) -> GraphResource {
+        self.screen
+    }
+
+    ///
+    /// Adds a resource which is owned outside of the graph, for example the shadow maps of the lights in the scene.
+    /// The graph does not allocate anything for an imported resource, it is only used to order the passes and to decide which passes to execute.
+    /// Since the content of an imported resource can be used after the graph has been executed, all passes which write an imported resource are executed.
+    ///
+    pub fn import(&mut self, name: &str) -> GraphResource {
+        self.add_resource(name, ResourceKind::Imported)
+    }
+
+    ///
+    /// Adds a transient color texture with the given size and format to the graph.
+    /// The texture uses nearest interpolation and clamp to edge wrapping.
+    ///
+    pub fn create_color_texture(
+        &mut self,
+        name: &str,
+        width: u32,
+        height: u32,
+        format: GraphColorFormat,
+    ) -> GraphResource {
+        self.add_resource(
+            name,
+            ResourceKind::Color {
+                width,
+                height,
+                format,
+            },
+        )
+    }
+
+    ///
+    /// Adds a transient 32 bit floating point depth texture with the given size to the graph.
+    /// The texture uses clamp to edge wrapping.
+    ///
+    pub fn create_depth_texture(&mut self, name: &str, width: u32, height: u32) -> GraphResource {
+        self.add_resource(name, ResourceKind::Depth { width, height })
+    }
+
+    ///
+    /// Returns the name of the given resource.
+    ///
+    pub fn resource_name(&self, resource: GraphResource) -> &str {
+        &self.resources[resource.0].name
+    }
+
+    ///
+    /// Adds a pass to the graph which reads the `reads` resources and writes the `writes` resources.
+    /// A pass cannot both read and write the same resource, and a texture must be written by an earlier pass before it is read.
+    /// Writing a texture does not clear it, so a pass can continue rendering into a texture written by an earlier pass, for example when rendering transparent objects on top of the opaque objects.
+    ///
+    /// The `execute` callback is called when the graph is executed, if the output of the pass is used.
+    /// It is given access to the textures the pass reads and writes through [PassInputs] and [PassOutputs].
+    ///
+    pub fn add_pass(
+        &mut self,
+        name: &str,
+        reads: &[GraphResource],
+        writes: &[GraphResource],
+        execute: impl FnOnce(&PassInputs<'_>, &mut PassOutputs<'_>) + 'a,
+    ) {
+        self.passes.push(Pass {
+            name: name.to_string(),
+            reads: reads.to_vec(),
+            writes: writes.to_vec(),
+            execute: Box::new(execute),
+        });
+    }
+
+    ///
+    /// Adds a pass which applies the given effect (see [RenderTarget::apply_screen_effect]) using the optional color and depth input textures
+    /// and writes the result to the given output, which is either a color texture created by this graph or the screen (see [RenderGraph::screen]).
+    /// Add several of these passes, where each pass reads the output of the previous, to create a post effect chain.
+    ///
+    pub fn add_effect_pass(
+        &mut self,
+        name: &str,
+        effect: &'a dyn Effect,
+        camera: &'a Camera,
+        lights: &'a [&'a dyn Light],
+        color_input: Option<GraphResource>,
+        depth_input: Option<GraphResource>,
+        output: GraphResource,
+    ) {
+        let reads = color_input
+            .iter()
+            .chain(depth_input.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let screen = self.screen;
+        self.add_pass(name, &reads, &[output], move |inputs, outputs| {
+            let color_texture = color_input.map(|r| ColorTexture::Single(inputs.color_texture(r)));
+            let depth_texture = depth_input.map(|r| DepthTexture::Single(inputs.depth_texture(r)));
+            if output == screen {
+                outputs.screen().apply_screen_effect(
+                    effect,
+                    camera,
+                    lights,
+                    color_texture,
+                    depth_texture,
+                );
+            } else {
+                outputs.color_target(output).apply_screen_effect(
+                    effect,
+                    camera,
+                    lights,
+                    color_texture,
+                    depth_texture,
+                );
+            }
+        });
+    }
+
+    ///
+    /// Executes the passes whose output is used in the order they were added, where the given render target is the target of the [RenderGraph::screen] resource.
+    /// Each executed pass is measured in a profiling scope with the name of the pass (see [Context::profile_scope]).
+    /// Returns an error if a pass both reads and writes the same resource or reads a texture which is not written by an earlier pass, in which case nothing is executed.
+    ///
+    pub fn execute(self, target: &RenderTarget) -> Result<(), RendererError> {
+        self.validate()?;
+        let executed = self.executed_passes();
+
+        // Find the first and last executed pass using each resource
+        let mut first_use = vec![None; self.resources.len()];
+        let mut last_use = vec![None; self.resources.len()];
+        for (i, pass) in self.passes.iter().enumerate().filter(|(i, _)| executed[*i]) {
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                first_use[resource.0].get_or_insert(i);
+                last_use[resource.0] = Some(i);
+            }
+        }
+
+        let mut slots = self
+            .resources
+            .iter()
+            .map(|_| Slot::Empty)
+            .collect::<Vec<_>>();
+        for (i, pass) in self.passes.into_iter().enumerate() {
+            if !executed[i] {
+                continue;
+            }
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                if first_use[resource.0] == Some(i) {
+                    slots[resource.0] = allocate(&self.context, self.resources[resource.0].kind);
+                }
+            }
+
+            {
+                let mut inputs = PassInputs {
+                    slots: Vec::new(),
+                    names: &self.resources,
+                };
+                let mut outputs = PassOutputs {
+                    slots: Vec::new(),
+                    names: &self.resources,
+                    screen: pass.writes.contains(&self.screen).then_some(target),
+                };
+                for (j, slot) in slots.iter_mut().enumerate() {
+                    let resource = GraphResource(j);
+                    if pass.reads.contains(&resource) {
+                        inputs.slots.push((resource, &*slot));
+                    } else if pass.writes.contains(&resource) {
+                        outputs.slots.push((resource, slot));
+                    }
+                }
+                let execute = pass.execute;
+                self.context
+                    .profile_scope(&pass.name, || execute(&inputs, &mut outputs));
+            }
+
+            // Return the textures which are no longer needed to the texture pool
+            for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                if last_use[resource.0] == Some(i) {
+                    slots[resource.0] = Slot::Empty;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_resource(&mut self, name: &str, kind: ResourceKind) -> GraphResource {
+        self.resources.push(Resource {
+            name: name.to_string(),
+            kind,
+        });
+        GraphResource(self.resources.len() - 1)
+    }
+
+    fn validate(&self) -> Result<(), RendererError> {
+        let mut written = self
+            .resources
+            .iter()
+            .map(|r| matches!(r.kind, ResourceKind::Imported))
+            .collect::<Vec<_>>();
+        for pass in self.passes.iter() {
+            for resource in pass.reads.iter() {
+                let name = &self.resources[resource.0].name;
+                if pass.writes.contains(resource) {
+                    return Err(RendererError::RenderGraphReadWrite(
+                        pass.name.clone(),
+                        name.clone(),
+                    ));
+                }
+                if !written[resource.0] {
+                    return Err(RendererError::RenderGraphMissingInput(
+                        pass.name.clone(),
+                        name.clone(),
+                    ));
+                }
+            }
+            for resource in pass.writes.iter() {
+                written[resource.0] = true;
+            }
+        }
+        Ok(())
+    }
+
+    fn executed_passes(&self) -> Vec<bool> {
+        // Go through the passes backwards and execute a pass if it writes a resource which is used later.
+        // Since writing does not clear a texture, the resources written by an executed pass are also used.
+        let mut used = self
+            .resources
+            .iter()
+            .map(|r| matches!(r.kind, ResourceKind::Imported))
+            .collect::<Vec<_>>();
+        let mut executed = vec![false; self.passes.len()];
+        for (i, pass) in self.passes.iter().enumerate().rev() {
+            if pass.writes.iter().any(|r| used[r.0]) {
+                executed[i] = true;
+                for resource in pass.reads.iter().chain(pass.writes.iter()) {
+                    used[resource.0] = true;
+                }
+            }
+        }
+        executed
+    }
+}
+
+fn allocate(context: &Context, kind: ResourceKind) -> Slot {
+    match kind {
+        ResourceKind::Color {
+            width,
+            height,
+            format,
+        } => {
+            let texture = match format {
+                GraphColorFormat::Rgba8 => context.transient_texture_2d::<[u8; 4]>(
+                    width,
+                    height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                GraphColorFormat::Rgba16F => context.transient_texture_2d::<[f16; 4]>(
+                    width,
+                    height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+                GraphColorFormat::Rgba32F => context.transient_texture_2d::<[f32; 4]>(
+                    width,
+                    height,
+                    Interpolation::Nearest,
+                    Interpolation::Nearest,
+                    None,
+                    Wrapping::ClampToEdge,
+                    Wrapping::ClampToEdge,
+                ),
+            };
+            Slot::Color(texture)
+        }
+        ResourceKind::Depth { width, height } => {
+            Slot::Depth(context.transient_depth_texture_2d::<f32>(
+                width,
+                height,
+                Wrapping::ClampToEdge,
+                Wrapping::ClampToEdge,
+            ))
+        }
+        ResourceKind::Imported => Slot::Empty,
+    }
+}
+
+///
+/// Gives a pass in a [RenderGraph] access to the textures it reads.
+///
+pub struct PassInputs<'b> {
+    slots: Vec<(GraphResource, &'b Slot)>,
+    names: &'b [Resource],
+}
+
+impl PassInputs<'_> {
+    ///
+    /// Returns the color texture for the given resource.
+    ///
+    /// **Panics:** If the resource is not a color texture or not read by this pass.
+    ///
+    pub fn color_texture(&self, resource: GraphResource) -> &Texture2D {
+        match self.slot(resource) {
+            Slot::Color(texture) => texture,
+            _ => panic!("{} is not a color texture", self.names[resource.0].name),
+        }
+    }
+
+    ///
+    /// Returns the depth texture for the given resource.
+    ///
+    /// **Panics:** If the resource is not a depth texture or not read by this pass.
+    ///
+    pub fn depth_texture(&self, resource: GraphResource) -> &DepthTexture2D {
+        match self.slot(resource) {
+            Slot::Depth(texture) => texture,
+            _ => panic!("{} is not a depth texture", self.names[resource.0].name),
+        }
+    }
+
+    fn slot(&self, resource: GraphResource) -> &Slot {
+        self.slots
+            .iter()
+            .find(|(r, _)| *r == resource)
+            .map(|(_, slot)| *slot)
+            .unwrap_or_else(|| panic!("{} is not read by this pass", self.names[resource.0].name))
+    }
+}
+
+///
+/// Gives a pass in a [RenderGraph] access to the textures and the render target it writes.
+///
+pub struct PassOutputs<'b> {
+    slots: Vec<(GraphResource, &'b mut Slot)>,
+    names: &'b [Resource],
+    screen: Option<&'b RenderTarget<'b>>,
+}
+
+impl PassOutputs<'_> {
+    ///
+    /// Returns the render target given to [RenderGraph::execute].
+    ///
+    /// **Panics:** If the [RenderGraph::screen] resource is not written by this pass.
+    ///
+    pub fn screen(&self) -> &RenderTarget<'_> {
+        self.screen.expect("the screen is not written by this pass")
+    }
+
+    ///
+    /// Returns a render target which writes to the given color and depth textures.
+    ///
+    /// **Panics:** If the resources are not a color and a depth texture written by this pass.
+    ///
+    pub fn render_target(
+        &mut self,
+        color: GraphResource,
+        depth: GraphResource,
+    ) -> RenderTarget<'_> {
+        let mut color_texture = None;
+        let mut depth_texture = None;
+        for (resource, slot) in self.slots.iter_mut() {
+            match slot {
+                Slot::Color(texture) if *resource == color => color_texture = Some(texture),
+                Slot::Depth(texture) if *resource == depth => depth_texture = Some(texture),
+                _ => {}
+            }
+        }
+        let color_texture = color_texture.unwrap_or_else(|| {
+            panic!(
+                "{} is not a color texture written by this pass",
+                self.names[color.0].name
+            )
+        });
+        let depth_texture = depth_texture.unwrap_or_else(|| {
+            panic!(
+                "{} is not a depth texture written by this pass",
+                self.names[depth.0].name
+            )
+        });
+        RenderTarget::new(
+            color_texture.as_color_target(None),
+            depth_texture.as_depth_target(),
+        )
+    }
+
+    ///
+    /// Returns a color target which writes to the given color texture.
+    ///
+    /// **Panics:** If the resource is not a color texture written by this pass.
+    ///
+    pub fn color_target(&mut self, color: GraphResource) -> ColorTarget<'_> {
+        let names = self.names;
+        self.slots
+            .iter_mut()
+            .find_map(|(resource, slot)| match slot {
+                Slot::Color(texture) if *resource == color => Some(texture.as_color_target(None)),
+                _ => None,
+            })
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} is not a color texture written by this pass",
+                    names[color.0].name
+                )
+            })
+    }
+
+    ///
+    /// Returns a depth target which writes to the given depth texture.
+    ///
+    /// **Panics:** If the resource is not a depth texture written by this pass.
+    ///
+    pub fn depth_target(&mut self, depth: GraphResource) -> DepthTarget<'_> {
+        let names = self.names;
+        self.slots
+            .iter_mut()
+            .find_map(|(resource, slot)| match slot {
+                Slot::Depth(texture) if *resource == depth => Some(texture.as_depth_target()),
+                _ => None,
+            })
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} is not a depth texture written by this pass",
+                    names[depth.0].name
+                )
+            })
+    }
+}
@@ -0,0 +1,117 @@
+use crate::renderer::*;
+
+///
+/// Identifies the state needed to render an object, ie. the shader program and the textures, see [Object::state_key].
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StateKey {
+    /// Identifies the shader program, ie. the combination of the geometry id, the material id and the light ids.
+    pub program: u64,
+    /// Identifies the textures used by the material, see [Material::texture_key].
+    pub textures: u64,
+}
+
+impl StateKey {
+    ///
+    /// Returns the state key for rendering the given geometry with the given material and lights.
+    ///
+    pub fn new(geometry: &impl Geometry, material: &dyn Material, lights: &[&dyn Light]) -> Self {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        program_key(
+            geometry.id(material.fragment_attributes()),
+            material.id(),
+            lights,
+        )
+        .hash(&mut hasher);
+        Self {
+            program: hasher.finish(),
+            textures: material.texture_key(),
+        }
+    }
+}
+
+const DEPTH_BUCKETS: f32 = 64.0;
+
+///
+/// A list of objects sorted so that they can be rendered efficiently and correctly.
+/// Opaque objects are grouped by their [StateKey], so that the same shader program and textures are used for as many objects in a row as possible,
+/// and within a group they are sorted from nearest to farthest away from the camera in a number of coarse depth buckets, to make use of early depth testing.
+/// Transparent objects are rendered after the opaque objects and sorted from farthest away to nearest to the camera, to blend them correctly.
+///
+/// This is the order used by the render calls, for example [RenderTarget::render].
+///
+pub struct RenderQueue<T: Object> {
+    objects: Vec<T>,
+}
+
+impl<T: Object> RenderQueue<T> {
+    ///
+    /// Sorts the given objects for rendering with the given camera and lights.
+    ///
+    pub fn new(
+        camera: &Camera,
+        objects: impl IntoIterator<Item = T>,
+        lights: &[&dyn Light],
+    ) -> Self {
+        let position = camera.position();
+        let depth_range = (camera.z_far() - camera.z_near()).max(f32::EPSILON);
+        let mut keyed_objects = objects
+            .into_iter()
+            .map(|object| {
+                let distance = position.distance(object.aabb().center());
+                let key = if object.material_type() == MaterialType::Transparent {
+                    // Farthest away first, the bits of a positive float are ordered in the same way as the float.
+                    (1, StateKey::default(), u32::MAX - distance_bits(distance))
+                } else {
+                    let bucket = if distance.is_nan() {
+                        u32::MAX
+                    } else {
+                        (distance / depth_range * DEPTH_BUCKETS).min(DEPTH_BUCKETS) as u32
+                    };
+                    (0, object.state_key(lights).unwrap_or_default(), bucket)
+                };
+                (key, object)
+            })
+            .collect::<Vec<_>>();
+        keyed_objects.sort_by_key(|(key, _)| *key);
+        Self {
+            objects: keyed_objects
+                .into_iter()
+                .map(|(_, object)| object)
+                .collect(),
+        }
+    }
+
+    ///
+    /// Renders the objects in the queue in the sorted order.
+    /// Must be called in the callback given as input to a [RenderTarget], [ColorTarget] or [DepthTarget] write method.
+    ///
+    pub fn render(&self, camera: &Camera, lights: &[&dyn Light]) {
+        for object in self.objects.iter() {
+            object.render(camera, lights);
+        }
+    }
+
+    ///
+    /// Returns the objects in the sorted order.
+    ///
+    pub fn into_inner(self) -> Vec<T> {
+        self.objects
+    }
+}
+
+impl<T: Object> std::ops::Deref for RenderQueue<T> {
+    type Target = [T];
+    fn deref(&self) -> &Self::Target {
+        &self.objects
+    }
+}
+
+fn distance_bits(distance: f32) -> u32 {
+    if distance.is_nan() {
+        0
+    } else {
+        distance.max(0.0).to_bits()
+    }
+}
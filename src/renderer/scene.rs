@@ -0,0 +1,604 @@
+use crate::renderer::*;
+
+///
+/// An [Object] which can be attached to a node in a [Scene].
+/// The transformation of the object is set to the world transformation of the node whenever the node is updated, see [Scene::update].
+///
+pub trait SceneObject: Object {
+    ///
+    /// Sets the local to world transformation of this object.
+    ///
+    fn set_transformation(&mut self, transformation: Mat4);
+
+    ///
+    /// Returns this object as an [Object].
+    ///
+    fn as_object(&self) -> &dyn Object;
+}
+
+macro_rules! impl_scene_object {
+    ($geometry:ty) => {
+        impl<M: Material> SceneObject for Gm<$geometry, M> {
+            fn set_transformation(&mut self, transformation: Mat4) {
+                self.geometry.set_transformation(transformation);
+            }
+
+            fn as_object(&self) -> &dyn Object {
+                self
+            }
+        }
+    };
+}
+
+impl_scene_object!(Mesh);
+impl_scene_object!(InstancedMesh);
+impl_scene_object!(Sprites);
+impl_scene_object!(ParticleSystem);
+
+impl<M: Material> SceneObject for ModelPart<M> {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        std::ops::DerefMut::deref_mut(self)
+            .geometry
+            .set_transformation(transformation);
+    }
+
+    fn as_object(&self) -> &dyn Object {
+        self
+    }
+}
+
+///
+/// A [Light] which can be attached to a node in a [Scene].
+/// Following the glTF convention, the light is placed at the origin of the node and shines in the direction of the negative Z axis of the node.
+///
+pub trait SceneLight: Light {
+    ///
+    /// Sets the local to world transformation of this light.
+    ///
+    fn set_transformation(&mut self, transformation: Mat4);
+
+    ///
+    /// Returns this light as a [Light].
+    ///
+    fn as_light(&self) -> &dyn Light;
+}
+
+impl SceneLight for AmbientLight {
+    fn set_transformation(&mut self, _transformation: Mat4) {}
+
+    fn as_light(&self) -> &dyn Light {
+        self
+    }
+}
+
+impl SceneLight for DirectionalLight {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.direction = direction_from_transformation(transformation);
+    }
+
+    fn as_light(&self) -> &dyn Light {
+        self
+    }
+}
+
+impl SceneLight for PointLight {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.position = position_from_transformation(transformation);
+    }
+
+    fn as_light(&self) -> &dyn Light {
+        self
+    }
+}
+
+impl SceneLight for SpotLight {
+    fn set_transformation(&mut self, transformation: Mat4) {
+        self.position = position_from_transformation(transformation);
+        self.direction = direction_from_transformation(transformation);
+    }
+
+    fn as_light(&self) -> &dyn Light {
+        self
+    }
+}
+
+fn position_from_transformation(transformation: Mat4) -> Vec3 {
+    (transformation * vec4(0.0, 0.0, 0.0, 1.0)).truncate()
+}
+
+fn direction_from_transformation(transformation: Mat4) -> Vec3 {
+    (transformation * vec4(0.0, 0.0, -1.0, 0.0))
+        .truncate()
+        .normalize()
+}
+
+fn up_from_transformation(transformation: Mat4) -> Vec3 {
+    (transformation * vec4(0.0, 1.0, 0.0, 0.0))
+        .truncate()
+        .normalize()
+}
+
+///
+/// A handle to a node in a [Scene].
+/// The handle contains a generation, so a handle to a removed node does not refer to a node which is added later, even if the new node is stored in the same slot.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+///
+/// A node in a [Scene] which has a transformation relative to its parent and optionally contains an object, a light and a camera.
+///
+pub struct Node {
+    /// The name of the node.
+    pub name: String,
+    transformation: Mat4,
+    world_transformation: Mat4,
+    dirty: bool,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    object: Option<Box<dyn SceneObject>>,
+    light: Option<Box<dyn SceneLight>>,
+    camera: Option<Camera>,
+}
+
+impl Node {
+    ///
+    /// Returns the transformation of this node relative to its parent.
+    ///
+    pub fn transformation(&self) -> Mat4 {
+        self.transformation
+    }
+
+    ///
+    /// Sets the transformation of this node relative to its parent.
+    /// The world transformation of this node and all of its descendants is updated the next time [Scene::update] is called.
+    ///
+    pub fn set_transformation(&mut self, transformation: Mat4) {
+        self.transformation = transformation;
+        self.dirty = true;
+    }
+
+    ///
+    /// Returns the local to world transformation of this node as it was the last time [Scene::update] was called.
+    ///
+    pub fn world_transformation(&self) -> Mat4 {
+        self.world_transformation
+    }
+
+    ///
+    /// Returns the parent of this node or `None` if this is a root node.
+    ///
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    ///
+    /// Returns the children of this node.
+    ///
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    ///
+    /// Returns the object attached to this node, if any.
+    ///
+    pub fn object(&self) -> Option<&dyn SceneObject> {
+        self.object.as_deref()
+    }
+
+    ///
+    /// Returns the object attached to this node, if any.
+    /// Note that the transformation of the object is overwritten by the world transformation of the node when the node is updated.
+    ///
+    pub fn object_mut(&mut self) -> Option<&mut (dyn SceneObject + 'static)> {
+        self.object.as_deref_mut()
+    }
+
+    ///
+    /// Attaches the object to this node, replacing any previously attached object.
+    ///
+    pub fn set_object(&mut self, object: impl SceneObject + 'static) {
+        self.object = Some(Box::new(object));
+        self.dirty = true;
+    }
+
+    ///
+    /// Removes the object attached to this node, if any, and returns it.
+    ///
+    pub fn take_object(&mut self) -> Option<Box<dyn SceneObject>> {
+        self.object.take()
+    }
+
+    ///
+    /// Returns the light attached to this node, if any.
+    ///
+    pub fn light(&self) -> Option<&dyn SceneLight> {
+        self.light.as_deref()
+    }
+
+    ///
+    /// Returns the light attached to this node, if any.
+    /// Note that the position and direction of the light is overwritten by the world transformation of the node when the node is updated.
+    ///
+    pub fn light_mut(&mut self) -> Option<&mut (dyn SceneLight + 'static)> {
+        self.light.as_deref_mut()
+    }
+
+    ///
+    /// Attaches the light to this node, replacing any previously attached light.
+    ///
+    pub fn set_light(&mut self, light: impl SceneLight + 'static) {
+        self.light = Some(Box::new(light));
+        self.dirty = true;
+    }
+
+    ///
+    /// Removes the light attached to this node, if any, and returns it.
+    ///
+    pub fn take_light(&mut self) -> Option<Box<dyn SceneLight>> {
+        self.light.take()
+    }
+
+    ///
+    /// Returns the camera attached to this node, if any.
+    ///
+    pub fn camera(&self) -> Option<&Camera> {
+        self.camera.as_ref()
+    }
+
+    ///
+    /// Returns the camera attached to this node, if any.
+    /// Note that the view of the camera is overwritten by the world transformation of the node when the node is updated.
+    ///
+    pub fn camera_mut(&mut self) -> Option<&mut Camera> {
+        self.camera.as_mut()
+    }
+
+    ///
+    /// Attaches the camera to this node, replacing any previously attached camera.
+    /// Following the glTF convention, the camera is placed at the origin of the node, looks in the direction of the negative Z axis and has the positive Y axis as up direction.
+    ///
+    pub fn set_camera(&mut self, camera: Camera) {
+        self.camera = Some(camera);
+        self.dirty = true;
+    }
+
+    ///
+    /// Removes the camera attached to this node, if any, and returns it.
+    ///
+    pub fn take_camera(&mut self) -> Option<Camera> {
+        self.camera.take()
+    }
+
+    fn apply_world_transformation(&mut self) {
+        let transformation = self.world_transformation;
+        if let Some(object) = self.object.as_mut() {
+            object.set_transformation(transformation);
+        }
+        if let Some(light) = self.light.as_mut() {
+            light.set_transformation(transformation);
+        }
+        if let Some(camera) = self.camera.as_mut() {
+            let position = position_from_transformation(transformation);
+            camera.set_view(
+                position,
+                position + direction_from_transformation(transformation),
+                up_from_transformation(transformation),
+            );
+        }
+    }
+}
+
+///
+/// A tree of [Node]s, each with a transformation relative to its parent node.
+/// The world transformation of a node is the transformation of its parent node multiplied by its own transformation
+/// and is applied to the object, light and camera attached to the node when calling [Scene::update].
+/// Only the nodes which has changed since the last update, and their descendants, are updated.
+///
+/// A reference to a scene can be used directly in a render call, for example [RenderTarget::render], which renders all of the objects in the scene.
+/// Use [Scene::lights] to get the lights in the scene.
+///
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Option<Node>>,
+    generations: Vec<u32>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+impl Scene {
+    ///
+    /// Constructs a new empty scene.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Adds a new empty node with the given name and transformation relative to the parent node.
+    /// If the parent is `None`, the node is added as a root node.
+    ///
+    /// **Panics:** If the parent node is not in this scene.
+    ///
+    pub fn add_node(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        transformation: Mat4,
+    ) -> NodeId {
+        let index = self.free.pop().unwrap_or(self.nodes.len());
+        if index == self.nodes.len() {
+            self.nodes.push(None);
+            self.generations.push(0);
+        }
+        let id = NodeId {
+            index,
+            generation: self.generations[index],
+        };
+        let node = Node {
+            name: name.into(),
+            transformation,
+            world_transformation: Mat4::identity(),
+            dirty: true,
+            parent,
+            children: Vec::new(),
+            object: None,
+            light: None,
+            camera: None,
+        };
+        self.children_mut(parent).push(id);
+        self.nodes[index] = Some(node);
+        id
+    }
+
+    ///
+    /// Adds a new node with the given name and transformation relative to the parent node and attaches the object to it.
+    ///
+    /// **Panics:** If the parent node is not in this scene.
+    ///
+    pub fn add_object(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        transformation: Mat4,
+        object: impl SceneObject + 'static,
+    ) -> NodeId {
+        let id = self.add_node(name, parent, transformation);
+        self.node_mut(id).unwrap().set_object(object);
+        id
+    }
+
+    ///
+    /// Adds a new node with the given name and transformation relative to the parent node and attaches the light to it.
+    ///
+    /// **Panics:** If the parent node is not in this scene.
+    ///
+    pub fn add_light(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        transformation: Mat4,
+        light: impl SceneLight + 'static,
+    ) -> NodeId {
+        let id = self.add_node(name, parent, transformation);
+        self.node_mut(id).unwrap().set_light(light);
+        id
+    }
+
+    ///
+    /// Adds a new node with the given name and transformation relative to the parent node and attaches the camera to it.
+    ///
+    /// **Panics:** If the parent node is not in this scene.
+    ///
+    pub fn add_camera(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        transformation: Mat4,
+        camera: Camera,
+    ) -> NodeId {
+        let id = self.add_node(name, parent, transformation);
+        self.node_mut(id).unwrap().set_camera(camera);
+        id
+    }
+
+    ///
+    /// Adds a new node with the given name and transformation relative to the parent node and adds a child node for each part of the model.
+    /// The transformation of each model part is used as the transformation of the child node relative to the new node.
+    /// Returns the id of the new node.
+    ///
+    /// **Panics:** If the parent node is not in this scene.
+    ///
+    pub fn add_model<M: Material + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<NodeId>,
+        transformation: Mat4,
+        mut model: Model<M>,
+    ) -> NodeId {
+        let name = name.into();
+        let id = self.add_node(name.clone(), parent, transformation);
+        for (i, part) in model.drain(..).enumerate() {
+            let part_transformation = part.transformation();
+            self.add_object(
+                format!("{} {}", name, i),
+                Some(id),
+                part_transformation,
+                part,
+            );
+        }
+        id
+    }
+
+    ///
+    /// Removes the node and all of its descendants from the scene.
+    /// Does nothing if the node is not in this scene.
+    ///
+    pub fn remove_node(&mut self, id: NodeId) {
+        if let Some(node) = self.node(id) {
+            let parent = node.parent;
+            self.children_mut(parent).retain(|c| *c != id);
+            let mut stack = vec![id];
+            while let Some(id) = stack.pop() {
+                if let Some(node) = self.nodes[id.index].take() {
+                    self.generations[id.index] = self.generations[id.index].wrapping_add(1);
+                    self.free.push(id.index);
+                    stack.extend(node.children);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Moves the node to be a child of the given parent node or a root node if the parent is `None`.
+    /// The transformation of the node is kept, so the world transformation of the node changes if the world transformation of the new parent is different.
+    ///
+    /// **Panics:** If one of the nodes is not in this scene or if the parent node is the node itself or one of its descendants.
+    ///
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        let mut ancestor = parent;
+        while let Some(a) = ancestor {
+            if a == id {
+                panic!("a node cannot be moved to be a child of itself or one of its descendants");
+            }
+            ancestor = self
+                .node(a)
+                .expect("the parent node is not in the scene")
+                .parent;
+        }
+        let node = self.node_mut(id).expect("the node is not in the scene");
+        let old_parent = std::mem::replace(&mut node.parent, parent);
+        node.dirty = true;
+        self.children_mut(old_parent).retain(|c| *c != id);
+        self.children_mut(parent).push(id);
+    }
+
+    ///
+    /// Returns the node with the given id or `None` if it is not in this scene.
+    ///
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        if self.generations.get(id.index) == Some(&id.generation) {
+            self.nodes[id.index].as_ref()
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Returns the node with the given id or `None` if it is not in this scene.
+    ///
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        if self.generations.get(id.index) == Some(&id.generation) {
+            self.nodes[id.index].as_mut()
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Returns the id of the first node with the given name or `None` if there is no such node in this scene.
+    ///
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|n| n.as_ref().map(|n| n.name == name).unwrap_or(false))
+            .map(|index| NodeId {
+                index,
+                generation: self.generations[index],
+            })
+    }
+
+    ///
+    /// Returns the root nodes of this scene, ie. the nodes without a parent.
+    ///
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    ///
+    /// Updates the world transformation of all nodes which has changed since the last update and all of their descendants
+    /// and applies it to the objects, lights and cameras attached to those nodes.
+    /// Call this after changing the transformations of the nodes and before rendering the scene.
+    ///
+    pub fn update(&mut self) {
+        let mut stack = self
+            .roots
+            .iter()
+            .rev()
+            .map(|id| (*id, Mat4::identity(), false))
+            .collect::<Vec<_>>();
+        while let Some((id, parent_transformation, parent_changed)) = stack.pop() {
+            let node = self.nodes[id.index].as_mut().unwrap();
+            let changed = parent_changed || node.dirty;
+            if changed {
+                node.world_transformation = parent_transformation * node.transformation;
+                node.dirty = false;
+                node.apply_world_transformation();
+            }
+            let world_transformation = node.world_transformation;
+            stack.extend(
+                node.children
+                    .iter()
+                    .rev()
+                    .map(|c| (*c, world_transformation, changed)),
+            );
+        }
+    }
+
+    ///
+    /// For updating the animation of all objects in the scene. The time parameter should be some continious time, for example the time since start.
+    ///
+    pub fn animate(&mut self, time: f32) {
+        self.nodes
+            .iter_mut()
+            .flatten()
+            .filter_map(|n| n.object.as_mut())
+            .for_each(|o| o.animate(time));
+    }
+
+    ///
+    /// Returns all of the objects in the scene.
+    ///
+    pub fn objects(&self) -> impl Iterator<Item = &dyn Object> + '_ {
+        self.nodes
+            .iter()
+            .flatten()
+            .filter_map(|n| n.object.as_deref())
+            .map(|o| o.as_object())
+    }
+
+    ///
+    /// Returns all of the lights in the scene, which can be used as the lights in a render call.
+    ///
+    pub fn lights(&self) -> Vec<&dyn Light> {
+        self.nodes
+            .iter()
+            .flatten()
+            .filter_map(|n| n.light.as_deref())
+            .map(|l| l.as_light())
+            .collect()
+    }
+
+    fn children_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId> {
+        if let Some(parent) = parent {
+            &mut self
+                .node_mut(parent)
+                .expect("the parent node is not in the scene")
+                .children
+        } else {
+            &mut self.roots
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Scene {
+    type Item = &'a dyn Object;
+    type IntoIter = std::vec::IntoIter<&'a dyn Object>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.objects().collect::<Vec<_>>().into_iter()
+    }
+}
@@ -0,0 +1,81 @@
+use crate::renderer::*;
+
+///
+/// A texture containing the screen space velocity of the objects, ie. how far each pixel has moved on the screen since the previous frame,
+/// caused by the movement of both the camera and the objects (see [VelocityMaterial]).
+/// The movement in UV coordinates is stored in the red and green channels.
+/// The velocity can be used for the [MotionBlurEffect] and other effects that combine several frames.
+///
+/// Call [VelocityBuffer::update] each frame with the same camera and objects as used for the final image.
+/// The texture is borrowed from the texture pool (see [Context::transient_texture_2d]).
+///
+pub struct VelocityBuffer {
+    context: Context,
+    texture: Option<TransientTexture<Texture2D>>,
+    previous_view_projection: Option<Mat4>,
+}
+
+impl VelocityBuffer {
+    ///
+    /// Creates a new velocity buffer.
+    ///
+    pub fn new(context: &Context) -> Self {
+        Self {
+            context: context.clone(),
+            texture: None,
+            previous_view_projection: None,
+        }
+    }
+
+    ///
+    /// Renders the velocity of the given objects since the previous call to this method.
+    /// The first time this method is called, the velocity is zero.
+    ///
+    pub fn update(&mut self, camera: &Camera, objects: impl IntoIterator<Item = impl Object>) {
+        let viewport = Viewport::new_at_origo(camera.viewport().width, camera.viewport().height);
+        let mut velocity_camera = camera.clone();
+        velocity_camera.set_viewport(viewport);
+        let view_projection = camera.projection() * camera.view();
+        let material = VelocityMaterial {
+            previous_view_projection: self.previous_view_projection.unwrap_or(view_projection),
+            ..Default::default()
+        };
+        let mut texture = self.context.transient_texture_2d::<[f16; 2]>(
+            viewport.width,
+            viewport.height,
+            Interpolation::Nearest,
+            Interpolation::Nearest,
+            None,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        let mut depth_texture = self.context.transient_depth_texture_2d::<f32>(
+            viewport.width,
+            viewport.height,
+            Wrapping::ClampToEdge,
+            Wrapping::ClampToEdge,
+        );
+        RenderTarget::new(
+            texture.as_color_target(None),
+            depth_texture.as_depth_target(),
+        )
+        .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
+        .write(|| {
+            for object in objects
+                .into_iter()
+                .filter(|o| is_visible(&velocity_camera, o))
+            {
+                object.render_with_material(&material, &velocity_camera, &[]);
+            }
+        });
+        self.texture = Some(texture);
+        self.previous_view_projection = Some(view_projection);
+    }
+
+    ///
+    /// Returns the velocity rendered in the last call to [VelocityBuffer::update].
+    ///
+    pub fn texture(&self) -> Option<&Texture2D> {
+        self.texture.as_deref()
+    }
+}
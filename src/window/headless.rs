@@ -40,11 +40,11 @@ impl HeadlessContext {
         let cb = ContextBuilder::new();
         let glutin_context = build_context(cb)?;
         let glutin_context = unsafe { glutin_context.make_current().map_err(|(_, e)| e)? };
+        let loader = |s: &str| glutin_context.get_proc_address(s);
         let context = Context::from_gl_context(std::sync::Arc::new(unsafe {
-            crate::context::Context::from_loader_function(|s| {
-                glutin_context.get_proc_address(s) as *const _
-            })
+            crate::context::Context::from_loader_function(loader)
         }))?;
+        unsafe { context.load_program_binary_functions(loader) };
         Ok(Self {
             context,
             _glutin_context: Rc::new(glutin_context),
@@ -64,8 +64,12 @@ impl FrameInputGenerator {
 
     ///
     /// Generates [FrameInput] for a new frame. This should be called each frame and the generated data should only be used for one frame.
+    /// Also resets the [RenderStats] of the given context, so that the statistics for the previous frame is available using [Context::previous_render_stats],
+    /// and trims the texture pool of the given context (see [Context::trim_texture_pool]).
     ///
     pub fn generate(&mut self, context: &Context) -> FrameInput {
+        context.reset_render_stats();
+        context.trim_texture_pool();
         let now = Instant::now();
         let duration = now.duration_since(self.last_time);
         let elapsed_time =